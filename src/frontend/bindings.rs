@@ -0,0 +1,113 @@
+extern crate sdl2;
+
+use std::collections::HashMap;
+use std::fs;
+
+use sdl2::controller::Button as PadButton;
+use sdl2::keyboard::Keycode;
+
+use crate::gameboy::Button;
+
+/// Keyboard and game controller mappings onto the joypad buttons.
+pub struct Bindings {
+    keys: HashMap<Keycode, Button>,
+    pad_buttons: HashMap<PadButton, Button>,
+}
+
+impl Bindings {
+    /// Arrow keys, X, Z, Backspace and Return, and the matching controller buttons.
+    pub fn new() -> Bindings {
+        let mut bindings = Bindings {
+            keys: HashMap::new(),
+            pad_buttons: HashMap::new(),
+        };
+
+        bindings.bind_key(Keycode::Right, Button::Right);
+        bindings.bind_key(Keycode::Left, Button::Left);
+        bindings.bind_key(Keycode::Up, Button::Up);
+        bindings.bind_key(Keycode::Down, Button::Down);
+        bindings.bind_key(Keycode::X, Button::A);
+        bindings.bind_key(Keycode::Z, Button::B);
+        bindings.bind_key(Keycode::Backspace, Button::Select);
+        bindings.bind_key(Keycode::Return, Button::Start);
+
+        bindings.bind_pad_button(PadButton::DPadRight, Button::Right);
+        bindings.bind_pad_button(PadButton::DPadLeft, Button::Left);
+        bindings.bind_pad_button(PadButton::DPadUp, Button::Up);
+        bindings.bind_pad_button(PadButton::DPadDown, Button::Down);
+        bindings.bind_pad_button(PadButton::A, Button::A);
+        bindings.bind_pad_button(PadButton::B, Button::B);
+        bindings.bind_pad_button(PadButton::Back, Button::Select);
+        bindings.bind_pad_button(PadButton::Start, Button::Start);
+
+        bindings
+    }
+
+    /// Map a key to a button, replacing any button it was bound to.
+    pub fn bind_key(&mut self, keycode: Keycode, button: Button) {
+        self.keys.insert(keycode, button);
+    }
+
+    /// Map a controller button to a button, replacing any button it was bound to.
+    pub fn bind_pad_button(&mut self, pad_button: PadButton, button: Button) {
+        self.pad_buttons.insert(pad_button, button);
+    }
+
+    /// Button bound to a key.
+    pub fn key(&self, keycode: Keycode) -> Option<Button> {
+        self.keys.get(&keycode).copied()
+    }
+
+    /// Button bound to a controller button.
+    pub fn pad_button(&self, pad_button: PadButton) -> Option<Button> {
+        self.pad_buttons.get(&pad_button).copied()
+    }
+
+    /// Load bindings on top of the defaults from a config file of the form
+    ///
+    /// ```text
+    /// # key.<button> = <SDL key name>, pad.<button> = <SDL controller button name>
+    /// key.a = X
+    /// key.start = Return
+    /// pad.a = b
+    /// ```
+    pub fn load(path: &str) -> Result<Bindings, String> {
+        let config: String = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let mut bindings: Bindings = Bindings::new();
+
+        for (line_number, line) in config.lines().enumerate() {
+            let line: &str = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = match line.find('=') {
+                Some(i) => (line[..i].trim(), line[i + 1..].trim()),
+                None => return Err(format!("{}:{}: expected key = value", path, line_number + 1)),
+            };
+
+            let (device, button_name) = match key.find('.') {
+                Some(i) => (&key[..i], &key[i + 1..]),
+                None => return Err(format!("{}:{}: expected key.<button> or pad.<button>", path, line_number + 1)),
+            };
+
+            let button: Button = match Button::from_name(button_name) {
+                Some(button) => button,
+                None => return Err(format!("{}:{}: unknown button {}", path, line_number + 1, button_name)),
+            };
+
+            match device {
+                "key" => match Keycode::from_name(value) {
+                    Some(keycode) => bindings.bind_key(keycode, button),
+                    None => return Err(format!("{}:{}: unknown key {}", path, line_number + 1, value)),
+                },
+                "pad" => match PadButton::from_string(value) {
+                    Some(pad_button) => bindings.bind_pad_button(pad_button, button),
+                    None => return Err(format!("{}:{}: unknown controller button {}", path, line_number + 1, value)),
+                },
+                _ => return Err(format!("{}:{}: unknown device {}", path, line_number + 1, device)),
+            }
+        }
+        Ok(bindings)
+    }
+}
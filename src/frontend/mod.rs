@@ -0,0 +1,42 @@
+//! Video, audio and input backends driven by the main loop.
+
+mod null;
+mod palette;
+mod resampler;
+#[cfg(feature = "sdl")]
+mod bindings;
+#[cfg(feature = "sdl")]
+mod window;
+
+use crate::gameboy::{EmulatorError, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+pub use null::NullFrontend;
+pub use palette::Palette;
+pub use resampler::Resampler;
+#[cfg(feature = "sdl")]
+pub use bindings::Bindings;
+#[cfg(feature = "sdl")]
+pub use window::SdlWindow;
+
+/// A backend that shows frames, plays audio and reads the buttons for a
+/// [`Gameboy`](crate::Gameboy). The main loop calls it once per frame.
+pub trait Frontend {
+    /// Show a finished frame of shades 0 (lightest) to 3 (darkest), row by row.
+    /// Frontends that run at the real speed wait here for the frame time.
+    fn present_frame(&mut self, frame_buffer: &[u8; SCREEN_WIDTH * SCREEN_HEIGHT]) -> Result<(), EmulatorError>;
+
+    /// Queue interleaved left/right samples at [`SAMPLE_RATE`](crate::SAMPLE_RATE) for playback.
+    fn queue_audio(&mut self, samples: &[f32]) -> Result<(), EmulatorError>;
+
+    /// Handle pending input events and return the mask of held buttons, see
+    /// [`Button::mask`](crate::Button::mask).
+    fn poll_input(&mut self) -> u8;
+
+    /// True once the user has asked to quit.
+    fn should_quit(&self) -> bool;
+
+    /// Mask of the sound channels the user has muted, bit 0 is channel 1.
+    fn muted_channels(&self) -> u8 {
+        0x00
+    }
+}
@@ -0,0 +1,36 @@
+use super::Frontend;
+use crate::gameboy::{EmulatorError, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+/// Headless frontend, drops frames and audio and never presses a button.
+pub struct NullFrontend;
+
+impl NullFrontend {
+    /// A frontend with nothing to show, play or read.
+    pub fn new() -> NullFrontend {
+        NullFrontend
+    }
+}
+
+impl Default for NullFrontend {
+    fn default() -> NullFrontend {
+        NullFrontend::new()
+    }
+}
+
+impl Frontend for NullFrontend {
+    fn present_frame(&mut self, _frame_buffer: &[u8; SCREEN_WIDTH * SCREEN_HEIGHT]) -> Result<(), EmulatorError> {
+        Ok(())
+    }
+
+    fn queue_audio(&mut self, _samples: &[f32]) -> Result<(), EmulatorError> {
+        Ok(())
+    }
+
+    fn poll_input(&mut self) -> u8 {
+        0x00
+    }
+
+    fn should_quit(&self) -> bool {
+        false
+    }
+}
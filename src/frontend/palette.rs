@@ -0,0 +1,105 @@
+use std::fs;
+
+/// RGB colours for the 4 DMG shades, lightest (shade 0) first.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Palette {
+    /// Shades 0 to 3 as (red, green, blue).
+    pub colors: [(u8, u8, u8); 4],
+}
+
+impl Palette {
+    /// Green tinted original DMG screen.
+    pub const DMG: Palette = Palette {
+        colors: [(0x9B, 0xBC, 0x0F), (0x8B, 0xAC, 0x0F), (0x30, 0x62, 0x30), (0x0F, 0x38, 0x0F)],
+    };
+
+    /// Grey Game Boy Pocket screen.
+    pub const POCKET: Palette = Palette {
+        colors: [(0xC4, 0xCF, 0xA1), (0x8B, 0x95, 0x6D), (0x4D, 0x53, 0x3C), (0x1F, 0x1F, 0x1F)],
+    };
+
+    /// Backlit Game Boy Light screen.
+    pub const LIGHT: Palette = Palette {
+        colors: [(0x00, 0xB5, 0x81), (0x00, 0x9A, 0x71), (0x00, 0x69, 0x4A), (0x00, 0x4F, 0x3B)],
+    };
+
+    /// Built-in palette by name: `dmg`, `pocket` or `light`.
+    pub fn from_name(name: &str) -> Option<Palette> {
+        match name.to_lowercase().as_str() {
+            "dmg" => Some(Palette::DMG),
+            "pocket" => Some(Palette::POCKET),
+            "light" => Some(Palette::LIGHT),
+            _ => None,
+        }
+    }
+
+    /// Next built-in palette, used to cycle through them from the keyboard.
+    pub fn next(&self) -> Palette {
+        if *self == Palette::DMG {
+            Palette::POCKET
+        } else if *self == Palette::POCKET {
+            Palette::LIGHT
+        } else {
+            Palette::DMG
+        }
+    }
+
+    /// Load a user-defined palette from a config file of the form
+    ///
+    /// ```text
+    /// # lightest to darkest
+    /// color0 = #E0F8D0
+    /// color1 = #88C070
+    /// color2 = #346856
+    /// color3 = #081820
+    /// ```
+    pub fn load(path: &str) -> Result<Palette, String> {
+        let config: String = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let mut colors: [Option<(u8, u8, u8)>; 4] = [None; 4];
+
+        for (line_number, line) in config.lines().enumerate() {
+            let line: &str = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = match line.find('=') {
+                Some(i) => (line[..i].trim(), line[i + 1..].trim()),
+                None => return Err(format!("{}:{}: expected key = value", path, line_number + 1)),
+            };
+
+            let shade: usize = match key {
+                "color0" => 0,
+                "color1" => 1,
+                "color2" => 2,
+                "color3" => 3,
+                _ => return Err(format!("{}:{}: unknown key {}", path, line_number + 1, key)),
+            };
+
+            colors[shade] = match parse_color(value) {
+                Some(color) => Some(color),
+                None => return Err(format!("{}:{}: invalid colour {}", path, line_number + 1, value)),
+            };
+        }
+
+        let mut palette: Palette = Palette::DMG;
+        for (shade, color) in colors.iter().enumerate() {
+            match color {
+                Some(color) => palette.colors[shade] = *color,
+                None => return Err(format!("{}: color{} missing", path, shade)),
+            }
+        }
+        Ok(palette)
+    }
+}
+
+// Parse #RRGGBB
+fn parse_color(value: &str) -> Option<(u8, u8, u8)> {
+    let hex: &str = value.strip_prefix('#').unwrap_or(value);
+    if hex.len() != 6 {
+        return None;
+    }
+
+    let rgb: u32 = u32::from_str_radix(hex, 16).ok()?;
+    Some(((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+}
@@ -0,0 +1,40 @@
+/// Linear interpolating resampler for interleaved stereo samples.
+pub struct Resampler {
+    // Input frames per output frame
+    step: f64,
+    // Position of the next output frame, 0.0 is the last frame of the previous input
+    position: f64,
+    previous: [f32; 2],
+}
+
+impl Resampler {
+    /// Convert from `input_rate` to `output_rate` samples per second.
+    pub fn new(input_rate: u32, output_rate: u32) -> Resampler {
+        Resampler {
+            step: input_rate as f64 / output_rate as f64,
+            position: 0.0,
+            previous: [0.0; 2],
+        }
+    }
+
+    /// Resample `input` and append the result to `output`.
+    pub fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        let frames: usize = input.len() / 2;
+        let frame = |i: usize| [input[i * 2], input[i * 2 + 1]];
+
+        while self.position < frames as f64 {
+            let i: usize = self.position as usize;
+            let t: f32 = (self.position - i as f64) as f32;
+            let a: [f32; 2] = if i == 0 { self.previous } else { frame(i - 1) };
+            let b: [f32; 2] = frame(i);
+            output.push(a[0] + (b[0] - a[0]) * t);
+            output.push(a[1] + (b[1] - a[1]) * t);
+            self.position += self.step;
+        }
+
+        if frames > 0 {
+            self.position -= frames as f64;
+            self.previous = frame(frames - 1);
+        }
+    }
+}
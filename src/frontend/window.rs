@@ -0,0 +1,292 @@
+extern crate sdl2;
+
+use sdl2::audio::{AudioQueue, AudioSpecDesired};
+use sdl2::controller::GameController;
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+use sdl2::rect::Point;
+use std::time::{Duration, Instant};
+
+use super::bindings::*;
+use super::palette::*;
+use super::resampler::*;
+use super::Frontend;
+use crate::gameboy::{EmulatorError, SAMPLE_RATE, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+// 17556 M-cycles at 1.048576 MHz
+const FRAME_DURATION: Duration = Duration::from_nanos(16_742_706);
+
+// Requested output format, SDL converts if the device differs
+const AUDIO_FREQUENCY: i32 = 48_000;
+const AUDIO_BUFFER_FRAMES: u16 = 1024;
+// Seconds of audio the queue is kept at
+const AUDIO_LATENCY: f64 = 0.05;
+// Largest change to the frame time made to hold the queue at AUDIO_LATENCY
+const MAX_RATE_DELTA: f64 = 0.005;
+
+/// SDL2 window with keyboard and controller input and queued audio output.
+pub struct SdlWindow {
+    canvas: sdl2::render::Canvas<sdl2::video::Window>,
+    event_pump: sdl2::EventPump,
+    controller_subsystem: sdl2::GameControllerSubsystem,
+    // Opened controllers stop reporting events once dropped
+    controllers: Vec<GameController>,
+    bindings: Bindings,
+    buttons: u8,
+    palette: Palette,
+    quit: bool,
+    frame_start: Instant,
+    audio: Option<AudioQueue<f32>>,
+    resampler: Resampler,
+    resampled: Vec<f32>,
+    volume: f32,
+    // Sound channels muted from the keyboard, bit 0 is channel 1
+    muted: u8,
+}
+
+impl SdlWindow {
+    /// Open a window `scale` times the size of the LCD. Runs without sound if
+    /// no audio device can be opened.
+    pub fn new(scale: u32) -> Result<SdlWindow, String> {
+        let sdl_context = sdl2::init()?;
+        let video_subsystem = sdl_context.video()?;
+    
+        let window = video_subsystem
+            .window("Gameboy", SCREEN_WIDTH as u32 * scale, SCREEN_HEIGHT as u32 * scale)
+            .position_centered()
+            .opengl()
+            .build()
+            .map_err(|e| e.to_string())?;
+    
+        let mut canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
+        canvas.set_scale(scale as f32, scale as f32)?;
+
+        canvas.set_draw_color(Color::RGB(0, 0, 0));
+        canvas.clear();
+        canvas.present();
+        let event_pump = sdl_context.event_pump()?;
+        let controller_subsystem = sdl_context.game_controller()?;
+
+        // Carry on without sound rather than fail on machines without an audio device
+        let audio: Option<AudioQueue<f32>> = match open_audio(&sdl_context) {
+            Ok(audio) => Some(audio),
+            Err(e) => {
+                eprintln!("No audio output: {}", e);
+                None
+            }
+        };
+        let output_rate: u32 = audio.as_ref().map_or(AUDIO_FREQUENCY, |audio| audio.spec().freq) as u32;
+
+        Ok(SdlWindow {
+            canvas: canvas,
+            event_pump: event_pump,
+            controller_subsystem: controller_subsystem,
+            controllers: Vec::new(),
+            bindings: Bindings::new(),
+            buttons: 0x00,
+            palette: Palette::DMG,
+            quit: false,
+            frame_start: Instant::now(),
+            audio: audio,
+            resampler: Resampler::new(SAMPLE_RATE, output_rate),
+            resampled: Vec::new(),
+            volume: 1.0,
+            muted: 0x00,
+        })
+    }
+
+    /// Replace the default key and controller bindings.
+    pub fn set_bindings(&mut self, bindings: Bindings) {
+        self.bindings = bindings;
+    }
+
+    // Mask of the joypad buttons currently held
+    fn buttons(&self) -> u8 {
+        self.buttons
+    }
+
+    /// Colours the 4 shades are drawn with.
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    /// Output volume from 0.0 (silent) to 1.0.
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.max(0.0).min(1.0);
+    }
+
+    fn event_loop(&mut self) -> bool {
+        for event in self.event_pump.poll_iter() {
+            match event {
+                Event::Quit { .. }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::Escape),
+                    ..
+                } => return false,
+                // Cycle through the built-in palettes
+                Event::KeyDown {
+                    keycode: Some(Keycode::P),
+                    ..
+                } => self.palette = self.palette.next(),
+                // Volume down and up in 10% steps
+                Event::KeyDown {
+                    keycode: Some(Keycode::Minus),
+                    ..
+                } => self.volume = (self.volume - 0.1).max(0.0),
+                Event::KeyDown {
+                    keycode: Some(Keycode::Equals),
+                    ..
+                } => self.volume = (self.volume + 0.1).min(1.0),
+                // Toggle muting sound channels 1-4
+                Event::KeyDown {
+                    keycode: Some(Keycode::Num1),
+                    ..
+                } => self.muted ^= 0x01,
+                Event::KeyDown {
+                    keycode: Some(Keycode::Num2),
+                    ..
+                } => self.muted ^= 0x02,
+                Event::KeyDown {
+                    keycode: Some(Keycode::Num3),
+                    ..
+                } => self.muted ^= 0x04,
+                Event::KeyDown {
+                    keycode: Some(Keycode::Num4),
+                    ..
+                } => self.muted ^= 0x08,
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    ..
+                } => {
+                    if let Some(button) = self.bindings.key(keycode) {
+                        self.buttons |= button.mask();
+                    }
+                }
+                Event::KeyUp {
+                    keycode: Some(keycode),
+                    ..
+                } => {
+                    if let Some(button) = self.bindings.key(keycode) {
+                        self.buttons &= !button.mask();
+                    }
+                }
+                Event::ControllerDeviceAdded { which, .. } => {
+                    if let Ok(controller) = self.controller_subsystem.open(which) {
+                        self.controllers.push(controller);
+                    }
+                }
+                Event::ControllerButtonDown { button, .. } => {
+                    if let Some(button) = self.bindings.pad_button(button) {
+                        self.buttons |= button.mask();
+                    }
+                }
+                Event::ControllerButtonUp { button, .. } => {
+                    if let Some(button) = self.bindings.pad_button(button) {
+                        self.buttons &= !button.mask();
+                    }
+                }
+                _ => {}
+            }
+        }
+        return true
+    }
+
+    fn display_loop(&mut self, frame_buffer: &[u8; SCREEN_WIDTH * SCREEN_HEIGHT]) -> Result<(), String> {
+        // Group the pixels by shade so each colour is drawn in one call
+        let mut points: [Vec<Point>; 4] = [Vec::new(), Vec::new(), Vec::new(), Vec::new()];
+        for (i, shade) in frame_buffer.iter().enumerate() {
+            let x: i32 = (i % SCREEN_WIDTH) as i32;
+            let y: i32 = (i / SCREEN_WIDTH) as i32;
+            points[*shade as usize & 0x03].push(Point::new(x, y));
+        }
+
+        for (shade, shade_points) in points.iter().enumerate() {
+            let (r, g, b) = self.palette.colors[shade];
+            self.canvas.set_draw_color(Color::RGB(r, g, b));
+            self.canvas.draw_points(&shade_points[..])?;
+        }
+        self.canvas.present();
+        Ok(())
+    }
+
+    // Sleep so frames are presented at the DMG refresh rate, stretched or
+    // shrunk slightly to hold the audio queue at AUDIO_LATENCY
+    fn wait_for_frame(&mut self) {
+        let frame_duration: Duration = match &self.audio {
+            Some(audio) => {
+                // More than the target queued slows emulation down, less speeds it up
+                let fill: f64 = audio.size() as f64 / target_queue_size(audio);
+                let adjust: f64 = (fill - 1.0).max(-1.0).min(1.0) * MAX_RATE_DELTA;
+                FRAME_DURATION.mul_f64(1.0 + adjust)
+            }
+            None => FRAME_DURATION,
+        };
+
+        let elapsed: Duration = self.frame_start.elapsed();
+        if elapsed < frame_duration {
+            std::thread::sleep(frame_duration - elapsed);
+        }
+        self.frame_start = Instant::now();
+    }
+}
+
+impl Frontend for SdlWindow {
+    fn present_frame(&mut self, frame_buffer: &[u8; SCREEN_WIDTH * SCREEN_HEIGHT]) -> Result<(), EmulatorError> {
+        self.display_loop(frame_buffer).map_err(EmulatorError::Frontend)?;
+        self.wait_for_frame();
+        Ok(())
+    }
+
+    fn queue_audio(&mut self, samples: &[f32]) -> Result<(), EmulatorError> {
+        let audio: &AudioQueue<f32> = match &self.audio {
+            Some(audio) => audio,
+            None => return Ok(()),
+        };
+
+        self.resampled.clear();
+        self.resampler.process(samples, &mut self.resampled);
+        for sample in self.resampled.iter_mut() {
+            *sample *= self.volume;
+        }
+
+        // Drop a backlog too large for rate control to catch up with, e.g. after the window was dragged
+        if audio.size() as f64 > target_queue_size(audio) * 4.0 {
+            audio.clear();
+        }
+        audio.queue_audio(&self.resampled).map_err(EmulatorError::Frontend)?;
+        audio.resume();
+        Ok(())
+    }
+
+    fn poll_input(&mut self) -> u8 {
+        if !self.event_loop() {
+            self.quit = true;
+        }
+        self.buttons()
+    }
+
+    fn should_quit(&self) -> bool {
+        self.quit
+    }
+
+    fn muted_channels(&self) -> u8 {
+        self.muted
+    }
+}
+
+fn open_audio(sdl_context: &sdl2::Sdl) -> Result<AudioQueue<f32>, String> {
+    let audio_subsystem = sdl_context.audio()?;
+    let desired = AudioSpecDesired {
+        freq: Some(AUDIO_FREQUENCY),
+        channels: Some(2),
+        samples: Some(AUDIO_BUFFER_FRAMES),
+    };
+    audio_subsystem.open_queue::<f32, _>(None, &desired)
+}
+
+// Bytes queued at AUDIO_LATENCY
+fn target_queue_size(audio: &AudioQueue<f32>) -> f64 {
+    let spec = audio.spec();
+    spec.freq as f64 * spec.channels as f64 * std::mem::size_of::<f32>() as f64 * AUDIO_LATENCY
+}
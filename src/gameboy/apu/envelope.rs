@@ -0,0 +1,54 @@
+// Volume envelope of the pulse and noise channels, NRx2
+pub struct Envelope {
+    initial_volume: u8,
+    increase: bool,
+    period: u8,
+    volume: u8,
+    timer: u8,
+}
+
+impl Envelope {
+    pub fn new() -> Envelope {
+        Envelope {
+            initial_volume: 0,
+            increase: false,
+            period: 0,
+            volume: 0,
+            timer: 0,
+        }
+    }
+
+    pub fn write(&mut self, d8: u8) {
+        self.initial_volume = d8 >> 4;
+        self.increase = d8 & 0x08 != 0;
+        self.period = d8 & 0x07;
+    }
+
+    pub fn trigger(&mut self) {
+        self.volume = self.initial_volume;
+        self.timer = self.period;
+    }
+
+    // Clocked at 64Hz, a period of 0 holds the volume
+    pub fn clock(&mut self) {
+        if self.period == 0 {
+            return;
+        }
+
+        if self.timer > 0 {
+            self.timer -= 1;
+        }
+        if self.timer == 0 {
+            self.timer = self.period;
+            if self.increase && self.volume < 0x0F {
+                self.volume += 1;
+            } else if !self.increase && self.volume > 0x00 {
+                self.volume -= 1;
+            }
+        }
+    }
+
+    pub fn volume(&self) -> u8 {
+        self.volume
+    }
+}
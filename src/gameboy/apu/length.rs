@@ -0,0 +1,43 @@
+// Turns a channel off after a programmed number of 256Hz frame sequencer clocks
+pub struct LengthCounter {
+    max: u16,
+    counter: u16,
+    enabled: bool,
+}
+
+impl LengthCounter {
+    // max is 64 for the pulse and noise channels and 256 for the wave channel
+    pub fn new(max: u16) -> LengthCounter {
+        LengthCounter {
+            max: max,
+            counter: 0,
+            enabled: false,
+        }
+    }
+
+    // NRx1 holds the length as max - counter
+    pub fn load(&mut self, length: u16) {
+        self.counter = self.max - length;
+    }
+
+    // NRx4 bit 6
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    // Triggering with an expired counter restarts it at the full length
+    pub fn trigger(&mut self) {
+        if self.counter == 0 {
+            self.counter = self.max;
+        }
+    }
+
+    // Returns true when the counter runs out and the channel should be disabled
+    pub fn clock(&mut self) -> bool {
+        if self.enabled && self.counter > 0 {
+            self.counter -= 1;
+            return self.counter == 0;
+        }
+        false
+    }
+}
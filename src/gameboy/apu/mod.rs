@@ -0,0 +1,340 @@
+mod envelope;
+mod length;
+mod noise;
+mod pulse;
+mod sweep;
+mod wave;
+
+use noise::*;
+use pulse::*;
+use wave::*;
+
+/// Stereo samples per second produced by the APU
+pub const SAMPLE_RATE: u32 = 65_536;
+// 1,048,576 M-cycles per second / SAMPLE_RATE
+const CYCLES_PER_SAMPLE: u32 = 16;
+// The frame sequencer steps at 512Hz
+const CYCLES_PER_SEQUENCER_STEP: u32 = 2048;
+// Stop collecting once a second of samples hasn't been taken
+const MAX_BUFFERED_SAMPLES: usize = SAMPLE_RATE as usize * 2;
+// Charge kept by the output capacitor per sample, 0.999958 per T-cycle
+const CHARGE_FACTOR: f32 = 0.997316;
+
+// Bits that always read back as 1 in NR10-NR52 (0xFF10-0xFF26)
+const READ_MASKS: [u8; 0x17] = [
+    0x80, 0x3F, 0x00, 0xFF, 0xBF, // NR10-NR14
+    0xFF, 0x3F, 0x00, 0xFF, 0xBF, // NR20-NR24
+    0x7F, 0xFF, 0x9F, 0xFF, 0xBF, // NR30-NR34
+    0xFF, 0xFF, 0x00, 0x00, 0xBF, // NR40-NR44
+    0x00, 0x00, 0x70, // NR50-NR52
+];
+
+const NR50: usize = 0x14;
+const NR51: usize = 0x15;
+
+// Sound registers 0xFF10-0xFF26 and wave RAM 0xFF30-0xFF3F
+pub struct Apu {
+    pulse1: Pulse,
+    pulse2: Pulse,
+    wave: Wave,
+    noise: Noise,
+    // Last values written to NR10-NR51
+    registers: [u8; 0x17],
+    powered: bool,
+    sequencer_cycles: u32,
+    sequencer_step: u8,
+    sample_cycles: u32,
+    capacitors: [f32; 2],
+    samples: Vec<f32>,
+    // Channels left out of the mix, bit 0 is channel 1
+    muted: u8,
+}
+
+impl Apu {
+    pub fn new() -> Apu {
+        Apu {
+            pulse1: Pulse::new(true),
+            pulse2: Pulse::new(false),
+            wave: Wave::new(),
+            noise: Noise::new(),
+            registers: [0u8; 0x17],
+            powered: false,
+            sequencer_cycles: 0,
+            sequencer_step: 0,
+            sample_cycles: 0,
+            capacitors: [0.0; 2],
+            samples: Vec::new(),
+            muted: 0x00,
+        }
+    }
+
+    pub fn read(&self, addr: u16) -> u8 {
+        match addr {
+            // NR52, power and the channel status bits
+            0xFF26 => {
+                let status: u8 = (self.pulse1.is_enabled() as u8)
+                    | (self.pulse2.is_enabled() as u8) << 1
+                    | (self.wave.is_enabled() as u8) << 2
+                    | (self.noise.is_enabled() as u8) << 3;
+                READ_MASKS[0x16] | (self.powered as u8) << 7 | status
+            }
+            0xFF10..=0xFF25 => {
+                let reg: usize = addr as usize - 0xFF10;
+                self.registers[reg] | READ_MASKS[reg]
+            }
+            0xFF30..=0xFF3F => self.wave.read_ram(addr),
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write(&mut self, addr: u16, d8: u8) {
+        match addr {
+            // Wave RAM is reachable with the APU off
+            0xFF30..=0xFF3F => self.wave.write_ram(addr, d8),
+            0xFF26 => self.set_power(d8 & 0x80 != 0),
+            // Everything else ignores writes while powered off
+            _ if !self.powered => {}
+            0xFF10..=0xFF25 => {
+                self.registers[addr as usize - 0xFF10] = d8;
+                match addr {
+                    0xFF10..=0xFF14 => self.pulse1.write(addr - 0xFF10, d8),
+                    0xFF15..=0xFF19 => self.pulse2.write(addr - 0xFF15, d8),
+                    0xFF1A..=0xFF1E => self.wave.write(addr - 0xFF1A, d8),
+                    0xFF1F..=0xFF23 => self.noise.write(addr - 0xFF1F, d8),
+                    // NR50 and NR51 are only read back when mixing
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Powering off clears NR10-NR51, powering on restarts the frame sequencer
+    fn set_power(&mut self, powered: bool) {
+        if self.powered && !powered {
+            self.registers = [0u8; 0x17];
+            self.pulse1 = Pulse::new(true);
+            self.pulse2 = Pulse::new(false);
+            self.wave.power_off();
+            self.noise = Noise::new();
+        } else if !self.powered && powered {
+            self.sequencer_cycles = 0;
+            self.sequencer_step = 0;
+        }
+        self.powered = powered;
+    }
+
+    // Advance by M-cycles, producing a sample every CYCLES_PER_SAMPLE
+    pub fn tick(&mut self, cycles: u32) {
+        for _ in 0..cycles {
+            if self.powered {
+                self.sequencer_cycles += 1;
+                if self.sequencer_cycles == CYCLES_PER_SEQUENCER_STEP {
+                    self.sequencer_cycles = 0;
+                    self.step_sequencer();
+                }
+
+                self.pulse1.tick(4);
+                self.pulse2.tick(4);
+                self.wave.tick(4);
+                self.noise.tick(4);
+            }
+
+            self.sample_cycles += 1;
+            if self.sample_cycles == CYCLES_PER_SAMPLE {
+                self.sample_cycles = 0;
+                self.mix();
+            }
+        }
+    }
+
+    // Length at 256Hz on even steps, sweep at 128Hz on steps 2 and 6, envelopes at 64Hz on step 7
+    fn step_sequencer(&mut self) {
+        if self.sequencer_step.is_multiple_of(2) {
+            self.pulse1.clock_length();
+            self.pulse2.clock_length();
+            self.wave.clock_length();
+            self.noise.clock_length();
+        }
+        if self.sequencer_step == 2 || self.sequencer_step == 6 {
+            self.pulse1.clock_sweep();
+        }
+        if self.sequencer_step == 7 {
+            self.pulse1.clock_envelope();
+            self.pulse2.clock_envelope();
+            self.noise.clock_envelope();
+        }
+        self.sequencer_step = (self.sequencer_step + 1) % 8;
+    }
+
+    // Pan the 4 channels through NR51, scale by the NR50 master volume and
+    // remove the DC offset like the output capacitor does
+    fn mix(&mut self) {
+        let outputs: [Option<u8>; 4] = [
+            self.pulse1.output(),
+            self.pulse2.output(),
+            self.wave.output(),
+            self.noise.output(),
+        ];
+        let panning: u8 = self.registers[NR51];
+        let volume: u8 = self.registers[NR50];
+
+        let mut left: f32 = 0.0;
+        let mut right: f32 = 0.0;
+        let mut dac_enabled: bool = false;
+        for (i, output) in outputs.iter().enumerate() {
+            if self.muted & (0x01 << i) != 0 {
+                continue;
+            }
+            if let Some(digital) = output {
+                dac_enabled = true;
+                // The DACs map 0-15 onto 1.0 to -1.0
+                let analog: f32 = 1.0 - *digital as f32 / 7.5;
+                if panning & (0x10 << i) != 0 {
+                    left += analog;
+                }
+                if panning & (0x01 << i) != 0 {
+                    right += analog;
+                }
+            }
+        }
+        left *= (((volume >> 4) & 0x07) + 1) as f32 / 8.0 / 4.0;
+        right *= ((volume & 0x07) + 1) as f32 / 8.0 / 4.0;
+
+        let left: f32 = self.high_pass(0, left, dac_enabled);
+        let right: f32 = self.high_pass(1, right, dac_enabled);
+        if self.samples.len() < MAX_BUFFERED_SAMPLES {
+            self.samples.push(left);
+            self.samples.push(right);
+        }
+    }
+
+    fn high_pass(&mut self, side: usize, input: f32, dac_enabled: bool) -> f32 {
+        if !dac_enabled {
+            return 0.0;
+        }
+        let output: f32 = input - self.capacitors[side];
+        self.capacitors[side] = input - output * CHARGE_FACTOR;
+        output
+    }
+
+    // Leave channels out of the mix, bit 0 is channel 1
+    pub fn set_muted(&mut self, muted: u8) {
+        self.muted = muted & 0x0F;
+    }
+
+    // Interleaved left/right samples since the last call
+    pub fn take_samples(&mut self) -> Vec<f32> {
+        std::mem::take(&mut self.samples)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn powered() -> Apu {
+        let mut apu: Apu = Apu::new();
+        apu.write(0xFF26, 0x80);
+        apu
+    }
+
+    fn channel_1_enabled(apu: &Apu) -> bool {
+        apu.read(0xFF26) & 0x01 != 0
+    }
+
+    #[test]
+    fn length_counter_expiry_clears_status_bit() {
+        let mut apu: Apu = powered();
+        // DAC on, length 63 of 64, trigger with the length counter enabled
+        apu.write(0xFF12, 0xF0);
+        apu.write(0xFF11, 0x3F);
+        apu.write(0xFF14, 0xC0);
+        assert!(channel_1_enabled(&apu));
+
+        apu.tick(CYCLES_PER_SEQUENCER_STEP - 1);
+        assert!(channel_1_enabled(&apu));
+        apu.tick(1);
+        assert!(!channel_1_enabled(&apu));
+    }
+
+    #[test]
+    fn sweep_overflow_on_trigger_disables_channel_1() {
+        let mut apu: Apu = powered();
+        // Shift 1 upwards from 0x7FF overflows on the first calculation
+        apu.write(0xFF10, 0x01);
+        apu.write(0xFF12, 0xF0);
+        apu.write(0xFF13, 0xFF);
+        apu.write(0xFF14, 0x87);
+        assert!(!channel_1_enabled(&apu));
+    }
+
+    #[test]
+    fn sweep_overflow_on_clock_disables_channel_1() {
+        let mut apu: Apu = powered();
+        // Period 1, shift 1 from 0x500: 0x780 fits, the check after it overflows
+        apu.write(0xFF10, 0x11);
+        apu.write(0xFF12, 0xF0);
+        apu.write(0xFF13, 0x00);
+        apu.write(0xFF14, 0x85);
+        assert!(channel_1_enabled(&apu));
+
+        // The sweep is clocked on the third sequencer step
+        apu.tick(CYCLES_PER_SEQUENCER_STEP * 2);
+        assert!(channel_1_enabled(&apu));
+        apu.tick(CYCLES_PER_SEQUENCER_STEP);
+        assert!(!channel_1_enabled(&apu));
+    }
+
+    #[test]
+    fn power_off_clears_registers_but_keeps_wave_ram() {
+        let mut apu: Apu = powered();
+        apu.write(0xFF24, 0x77);
+        apu.write(0xFF25, 0xFF);
+        apu.write(0xFF17, 0xF3);
+        apu.write(0xFF30, 0xAB);
+
+        apu.write(0xFF26, 0x00);
+        assert_eq!(apu.read(0xFF24), 0x00);
+        assert_eq!(apu.read(0xFF25), 0x00);
+        assert_eq!(apu.read(0xFF17), 0x00);
+        assert_eq!(apu.read(0xFF30), 0xAB);
+        assert_eq!(apu.read(0xFF26), 0x70);
+
+        // Registers ignore writes until powered on again
+        apu.write(0xFF24, 0x77);
+        assert_eq!(apu.read(0xFF24), 0x00);
+        apu.write(0xFF26, 0x80);
+        apu.write(0xFF24, 0x77);
+        assert_eq!(apu.read(0xFF24), 0x77);
+    }
+
+    // One mixed sample of channel 2 with its DAC on, which outputs 1.0 until triggered
+    fn mix_channel_2(nr50: u8, nr51: u8, muted: u8) -> Vec<f32> {
+        let mut apu: Apu = powered();
+        apu.write(0xFF17, 0xF0);
+        apu.write(0xFF24, nr50);
+        apu.write(0xFF25, nr51);
+        apu.set_muted(muted);
+        apu.mix();
+        apu.take_samples()
+    }
+
+    #[test]
+    fn mix_pans_through_nr51() {
+        assert_eq!(mix_channel_2(0x77, 0x20, 0x00), vec![0.25, 0.0]);
+        assert_eq!(mix_channel_2(0x77, 0x02, 0x00), vec![0.0, 0.25]);
+        assert_eq!(mix_channel_2(0x77, 0x11, 0x00), vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn mix_scales_by_nr50_volume() {
+        assert_eq!(mix_channel_2(0x70, 0x22, 0x00), vec![0.25, 0.03125]);
+        assert_eq!(mix_channel_2(0x03, 0x22, 0x00), vec![0.03125, 0.125]);
+    }
+
+    #[test]
+    fn muted_channels_are_left_out_of_the_mix() {
+        assert_eq!(mix_channel_2(0x77, 0x22, 0x02), vec![0.0, 0.0]);
+    }
+}
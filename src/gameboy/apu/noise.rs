@@ -0,0 +1,114 @@
+use super::envelope::*;
+use super::length::*;
+
+// T-cycles per LFSR step before the shift, indexed by NR43 bits 0-2
+const DIVISORS: [u32; 8] = [8, 16, 32, 48, 64, 80, 96, 112];
+
+// Channel 4, pseudo random noise from a 15 (or 7) bit linear feedback shift register
+pub struct Noise {
+    enabled: bool,
+    dac_enabled: bool,
+    shift: u8,
+    short_mode: bool,
+    divisor: u8,
+    timer: u32,
+    lfsr: u16,
+    length: LengthCounter,
+    envelope: Envelope,
+}
+
+impl Noise {
+    pub fn new() -> Noise {
+        Noise {
+            enabled: false,
+            dac_enabled: false,
+            shift: 0,
+            short_mode: false,
+            divisor: 0,
+            timer: 8,
+            lfsr: 0x7FFF,
+            length: LengthCounter::new(64),
+            envelope: Envelope::new(),
+        }
+    }
+
+    // reg is the offset from the unused 0xFF1F, NR41 is 1
+    pub fn write(&mut self, reg: u16, d8: u8) {
+        match reg {
+            0 => {}
+            1 => self.length.load(d8 as u16 & 0x3F),
+            2 => {
+                self.envelope.write(d8);
+                self.dac_enabled = d8 & 0xF8 != 0;
+                if !self.dac_enabled {
+                    self.enabled = false;
+                }
+            }
+            3 => {
+                self.shift = d8 >> 4;
+                self.short_mode = d8 & 0x08 != 0;
+                self.divisor = d8 & 0x07;
+            }
+            _ => {
+                self.length.set_enabled(d8 & 0x40 != 0);
+                if d8 & 0x80 != 0 {
+                    self.trigger();
+                }
+            }
+        }
+    }
+
+    fn trigger(&mut self) {
+        self.enabled = self.dac_enabled;
+        self.length.trigger();
+        self.timer = self.period();
+        self.envelope.trigger();
+        self.lfsr = 0x7FFF;
+    }
+
+    // T-cycles per LFSR step
+    fn period(&self) -> u32 {
+        DIVISORS[self.divisor as usize] << self.shift
+    }
+
+    pub fn tick(&mut self, t_cycles: u32) {
+        let mut t_cycles: u32 = t_cycles;
+        while t_cycles >= self.timer {
+            t_cycles -= self.timer;
+            self.timer = self.period();
+
+            // XOR of the low two bits is shifted in at bit 14, and also bit 6 in 7 bit mode
+            let feedback: u16 = (self.lfsr ^ (self.lfsr >> 1)) & 0x01;
+            self.lfsr = (self.lfsr >> 1) | (feedback << 14);
+            if self.short_mode {
+                self.lfsr = (self.lfsr & !0x40) | (feedback << 6);
+            }
+        }
+        self.timer -= t_cycles;
+    }
+
+    pub fn clock_length(&mut self) {
+        if self.length.clock() {
+            self.enabled = false;
+        }
+    }
+
+    pub fn clock_envelope(&mut self) {
+        self.envelope.clock();
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    // Digital output 0-15, None while the DAC is off
+    pub fn output(&self) -> Option<u8> {
+        if !self.dac_enabled {
+            return None;
+        }
+        if !self.enabled || self.lfsr & 0x01 == 0x01 {
+            return Some(0);
+        }
+        Some(self.envelope.volume())
+    }
+}
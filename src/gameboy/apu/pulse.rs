@@ -0,0 +1,131 @@
+use super::envelope::*;
+use super::length::*;
+use super::sweep::*;
+
+// 12.5%, 25%, 50% and 75% duty cycles
+const DUTY_PATTERNS: [[u8; 8]; 4] = [
+    [0, 0, 0, 0, 0, 0, 0, 1],
+    [1, 0, 0, 0, 0, 0, 0, 1],
+    [1, 0, 0, 0, 0, 1, 1, 1],
+    [0, 1, 1, 1, 1, 1, 1, 0],
+];
+
+// Square wave channels 1 (with sweep) and 2
+pub struct Pulse {
+    enabled: bool,
+    dac_enabled: bool,
+    duty: u8,
+    duty_step: usize,
+    frequency: u16,
+    timer: u32,
+    length: LengthCounter,
+    envelope: Envelope,
+    sweep: Option<Sweep>,
+}
+
+impl Pulse {
+    pub fn new(has_sweep: bool) -> Pulse {
+        Pulse {
+            enabled: false,
+            dac_enabled: false,
+            duty: 0,
+            duty_step: 0,
+            frequency: 0,
+            timer: 8192,
+            length: LengthCounter::new(64),
+            envelope: Envelope::new(),
+            sweep: if has_sweep { Some(Sweep::new()) } else { None },
+        }
+    }
+
+    // reg is the offset from NRx0
+    pub fn write(&mut self, reg: u16, d8: u8) {
+        match reg {
+            0 => {
+                if let Some(sweep) = self.sweep.as_mut() {
+                    sweep.write(d8);
+                }
+            }
+            1 => {
+                self.duty = d8 >> 6;
+                self.length.load(d8 as u16 & 0x3F);
+            }
+            2 => {
+                self.envelope.write(d8);
+                // The upper 5 bits power the DAC, turning it off disables the channel
+                self.dac_enabled = d8 & 0xF8 != 0;
+                if !self.dac_enabled {
+                    self.enabled = false;
+                }
+            }
+            3 => self.frequency = (self.frequency & 0x0700) | d8 as u16,
+            _ => {
+                self.frequency = (self.frequency & 0x00FF) | ((d8 as u16 & 0x07) << 8);
+                self.length.set_enabled(d8 & 0x40 != 0);
+                if d8 & 0x80 != 0 {
+                    self.trigger();
+                }
+            }
+        }
+    }
+
+    fn trigger(&mut self) {
+        self.enabled = self.dac_enabled;
+        self.length.trigger();
+        self.timer = self.period();
+        self.envelope.trigger();
+        if let Some(sweep) = self.sweep.as_mut() {
+            if !sweep.trigger(self.frequency) {
+                self.enabled = false;
+            }
+        }
+    }
+
+    // T-cycles per duty step
+    fn period(&self) -> u32 {
+        (2048 - self.frequency as u32) * 4
+    }
+
+    pub fn tick(&mut self, t_cycles: u32) {
+        let mut t_cycles: u32 = t_cycles;
+        while t_cycles >= self.timer {
+            t_cycles -= self.timer;
+            self.timer = self.period();
+            self.duty_step = (self.duty_step + 1) % 8;
+        }
+        self.timer -= t_cycles;
+    }
+
+    pub fn clock_length(&mut self) {
+        if self.length.clock() {
+            self.enabled = false;
+        }
+    }
+
+    pub fn clock_envelope(&mut self) {
+        self.envelope.clock();
+    }
+
+    pub fn clock_sweep(&mut self) {
+        if let Some(sweep) = self.sweep.as_mut() {
+            if !sweep.clock(&mut self.frequency) {
+                self.enabled = false;
+            }
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    // Digital output 0-15, None while the DAC is off
+    pub fn output(&self) -> Option<u8> {
+        if !self.dac_enabled {
+            return None;
+        }
+        if !self.enabled {
+            return Some(0);
+        }
+        Some(DUTY_PATTERNS[self.duty as usize][self.duty_step] * self.envelope.volume())
+    }
+}
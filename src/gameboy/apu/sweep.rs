@@ -0,0 +1,119 @@
+// Highest value of the 11 bit frequency
+const MAX_FREQUENCY: u16 = 0x07FF;
+
+// Frequency sweep of channel 1, NR10
+pub struct Sweep {
+    period: u8,
+    negate: bool,
+    shift: u8,
+    timer: u8,
+    shadow: u16,
+    enabled: bool,
+}
+
+impl Sweep {
+    pub fn new() -> Sweep {
+        Sweep {
+            period: 0,
+            negate: false,
+            shift: 0,
+            timer: 0,
+            shadow: 0,
+            enabled: false,
+        }
+    }
+
+    pub fn write(&mut self, d8: u8) {
+        self.period = (d8 >> 4) & 0x07;
+        self.negate = d8 & 0x08 != 0;
+        self.shift = d8 & 0x07;
+    }
+
+    // Returns false if the first calculation overflows and the channel should be disabled
+    pub fn trigger(&mut self, frequency: u16) -> bool {
+        self.shadow = frequency;
+        self.timer = self.reload();
+        self.enabled = self.period != 0 || self.shift != 0;
+        if self.shift != 0 {
+            return self.next_frequency().is_some();
+        }
+        true
+    }
+
+    // Clocked at 128Hz, updates frequency and returns false if the channel should be disabled
+    pub fn clock(&mut self, frequency: &mut u16) -> bool {
+        if self.timer > 0 {
+            self.timer -= 1;
+        }
+        if self.timer != 0 {
+            return true;
+        }
+
+        self.timer = self.reload();
+        if !self.enabled || self.period == 0 {
+            return true;
+        }
+
+        match self.next_frequency() {
+            Some(next) => {
+                if self.shift != 0 {
+                    self.shadow = next;
+                    *frequency = next;
+                    // The new frequency is checked for overflow again straight away
+                    return self.next_frequency().is_some();
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    // A period of 0 is treated as 8
+    fn reload(&self) -> u8 {
+        if self.period == 0 { 8 } else { self.period }
+    }
+
+    // None when the result overflows 11 bits
+    fn next_frequency(&self) -> Option<u16> {
+        let delta: u16 = self.shadow >> self.shift;
+        let next: u16 = if self.negate { self.shadow - delta } else { self.shadow + delta };
+        if next > MAX_FREQUENCY {
+            return None;
+        }
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trigger_reports_overflow_of_the_first_calculation() {
+        let mut sweep: Sweep = Sweep::new();
+        sweep.write(0x01);
+        assert!(!sweep.trigger(0x07FF));
+        assert!(sweep.trigger(0x0500));
+    }
+
+    #[test]
+    fn clock_updates_frequency_then_reports_overflow() {
+        let mut sweep: Sweep = Sweep::new();
+        sweep.write(0x11);
+        let mut frequency: u16 = 0x0500;
+        assert!(sweep.trigger(frequency));
+
+        assert!(!sweep.clock(&mut frequency));
+        assert_eq!(frequency, 0x0780);
+    }
+
+    #[test]
+    fn negate_never_overflows() {
+        let mut sweep: Sweep = Sweep::new();
+        sweep.write(0x19);
+        let mut frequency: u16 = 0x07FF;
+        assert!(sweep.trigger(frequency));
+        assert!(sweep.clock(&mut frequency));
+        assert_eq!(frequency, 0x0400);
+    }
+}
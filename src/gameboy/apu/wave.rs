@@ -0,0 +1,118 @@
+use super::length::*;
+
+// Channel 3, plays 32 4bit samples from wave RAM 0xFF30-0xFF3F
+pub struct Wave {
+    enabled: bool,
+    dac_enabled: bool,
+    volume_code: u8,
+    frequency: u16,
+    timer: u32,
+    position: usize,
+    sample: u8,
+    ram: [u8; 0x10],
+    length: LengthCounter,
+}
+
+impl Wave {
+    pub fn new() -> Wave {
+        Wave {
+            enabled: false,
+            dac_enabled: false,
+            volume_code: 0,
+            frequency: 0,
+            timer: 4096,
+            position: 0,
+            sample: 0,
+            ram: [0u8; 0x10],
+            length: LengthCounter::new(256),
+        }
+    }
+
+    // reg is the offset from NR30
+    pub fn write(&mut self, reg: u16, d8: u8) {
+        match reg {
+            0 => {
+                self.dac_enabled = d8 & 0x80 != 0;
+                if !self.dac_enabled {
+                    self.enabled = false;
+                }
+            }
+            1 => self.length.load(d8 as u16),
+            2 => self.volume_code = (d8 >> 5) & 0x03,
+            3 => self.frequency = (self.frequency & 0x0700) | d8 as u16,
+            _ => {
+                self.frequency = (self.frequency & 0x00FF) | ((d8 as u16 & 0x07) << 8);
+                self.length.set_enabled(d8 & 0x40 != 0);
+                if d8 & 0x80 != 0 {
+                    self.trigger();
+                }
+            }
+        }
+    }
+
+    pub fn read_ram(&self, addr: u16) -> u8 {
+        self.ram[addr as usize - 0xFF30]
+    }
+    pub fn write_ram(&mut self, addr: u16, d8: u8) {
+        self.ram[addr as usize - 0xFF30] = d8;
+    }
+
+    // Powering the APU off resets everything but wave RAM
+    pub fn power_off(&mut self) {
+        *self = Wave {
+            ram: self.ram,
+            ..Wave::new()
+        };
+    }
+
+    fn trigger(&mut self) {
+        self.enabled = self.dac_enabled;
+        self.length.trigger();
+        self.timer = self.period();
+        self.position = 0;
+    }
+
+    // T-cycles per sample
+    fn period(&self) -> u32 {
+        (2048 - self.frequency as u32) * 2
+    }
+
+    pub fn tick(&mut self, t_cycles: u32) {
+        let mut t_cycles: u32 = t_cycles;
+        while t_cycles >= self.timer {
+            t_cycles -= self.timer;
+            self.timer = self.period();
+            self.position = (self.position + 1) % 32;
+
+            // High nibble first
+            let byte: u8 = self.ram[self.position / 2];
+            self.sample = if self.position.is_multiple_of(2) { byte >> 4 } else { byte & 0x0F };
+        }
+        self.timer -= t_cycles;
+    }
+
+    pub fn clock_length(&mut self) {
+        if self.length.clock() {
+            self.enabled = false;
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    // Digital output 0-15, None while the DAC is off
+    pub fn output(&self) -> Option<u8> {
+        if !self.dac_enabled {
+            return None;
+        }
+        if !self.enabled {
+            return Some(0);
+        }
+        // Volume code 0 mutes, 1-3 shift the sample right by 0-2
+        match self.volume_code {
+            0 => Some(0),
+            code => Some(self.sample >> (code - 1)),
+        }
+    }
+}
@@ -0,0 +1,504 @@
+use std::fmt;
+
+use super::*;
+
+/// The header occupies 0x0100-0x014F
+pub const HEADER_END: usize = 0x0150;
+
+/// CGB flag 0x0143
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CgbSupport {
+    None,
+    Compatible,
+    Only,
+}
+
+/// Cartridge header at 0x0100-0x014F
+#[derive(Clone, Debug)]
+pub struct CartridgeHeader {
+    pub title: String,
+    pub manufacturer_code: Option<String>,
+    pub cgb_support: CgbSupport,
+    pub new_licensee_code: String,
+    pub sgb_support: bool,
+    pub cartridge_type: u8,
+    pub rom_size: u8,
+    pub ram_size: u8,
+    pub destination: u8,
+    pub old_licensee_code: u8,
+    pub version: u8,
+    pub header_checksum: u8,
+    pub global_checksum: u16,
+    // Checksums computed over the ROM
+    computed_header_checksum: u8,
+    computed_global_checksum: u16,
+}
+
+impl CartridgeHeader {
+    /// Decode the header, None if the ROM is too short to contain one
+    pub fn parse(rom: &[u8]) -> Option<CartridgeHeader> {
+        if rom.len() < HEADER_END {
+            return None;
+        }
+
+        let cgb_support: CgbSupport = match rom[0x0143] {
+            0xC0 => CgbSupport::Only,
+            0x80 => CgbSupport::Compatible,
+            _ => CgbSupport::None,
+        };
+
+        // CGB era carts shortened the title to fit a manufacturer code and the CGB flag
+        let (title_end, manufacturer_code) = if cgb_support == CgbSupport::None {
+            (0x0144, None)
+        } else {
+            let code: &[u8] = &rom[0x013F..0x0143];
+            if code.iter().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()) {
+                (0x013F, Some(ascii(code)))
+            } else {
+                (0x0143, None)
+            }
+        };
+
+        let computed_header_checksum: u8 = rom[0x0134..=0x014C]
+            .iter()
+            .fold(0u8, |x, b| x.wrapping_sub(*b).wrapping_sub(1));
+        let computed_global_checksum: u16 = rom
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != 0x014E && *i != 0x014F)
+            .fold(0u16, |x, (_, b)| x.wrapping_add(*b as u16));
+
+        Some(CartridgeHeader {
+            title: ascii(&rom[0x0134..title_end]),
+            manufacturer_code: manufacturer_code,
+            cgb_support: cgb_support,
+            new_licensee_code: ascii(&rom[0x0144..0x0146]),
+            sgb_support: rom[0x0146] == 0x03,
+            cartridge_type: rom[0x0147],
+            rom_size: rom[0x0148],
+            ram_size: rom[0x0149],
+            destination: rom[0x014A],
+            old_licensee_code: rom[0x014B],
+            version: rom[0x014C],
+            header_checksum: rom[0x014D],
+            global_checksum: ((rom[0x014E] as u16) << 8) | rom[0x014F] as u16,
+            computed_header_checksum: computed_header_checksum,
+            computed_global_checksum: computed_global_checksum,
+        })
+    }
+
+    /// Number of 16KB ROM banks
+    pub fn rom_banks(&self) -> Option<usize> {
+        match self.rom_size {
+            0x00..=0x08 => Some(2 << self.rom_size),
+            0x52 => Some(72),
+            0x53 => Some(80),
+            0x54 => Some(96),
+            _ => None,
+        }
+    }
+
+    /// Number of 8KB RAM banks
+    pub fn ram_banks(&self) -> Option<usize> {
+        match self.ram_size {
+            0x00 => Some(0),
+            0x01 | 0x02 => Some(1),
+            0x03 => Some(4),
+            0x04 => Some(16),
+            0x05 => Some(8),
+            _ => None,
+        }
+    }
+
+    pub fn has_battery(&self) -> bool {
+        matches!(
+            self.cartridge_type,
+            0x03 | 0x06 | 0x09 | 0x0D | 0x0F | 0x10 | 0x13 | 0x1B | 0x1E | 0x22 | 0xFF
+        )
+    }
+
+    pub fn has_rtc(&self) -> bool {
+        self.cartridge_type == 0x0F || self.cartridge_type == 0x10
+    }
+
+    /// The boot ROM refuses to start a cart with a bad header checksum
+    pub fn header_checksum_valid(&self) -> bool {
+        self.header_checksum == self.computed_header_checksum
+    }
+
+    /// Not checked by the hardware
+    pub fn global_checksum_valid(&self) -> bool {
+        self.global_checksum == self.computed_global_checksum
+    }
+
+    pub fn cartridge_type_name(&self) -> &'static str {
+        match self.cartridge_type {
+            0x00 => "ROM ONLY",
+            0x01 => "MBC1",
+            0x02 => "MBC1+RAM",
+            0x03 => "MBC1+RAM+BATTERY",
+            0x05 => "MBC2",
+            0x06 => "MBC2+BATTERY",
+            0x08 => "ROM+RAM",
+            0x09 => "ROM+RAM+BATTERY",
+            0x0B => "MMM01",
+            0x0C => "MMM01+RAM",
+            0x0D => "MMM01+RAM+BATTERY",
+            0x0F => "MBC3+TIMER+BATTERY",
+            0x10 => "MBC3+TIMER+RAM+BATTERY",
+            0x11 => "MBC3",
+            0x12 => "MBC3+RAM",
+            0x13 => "MBC3+RAM+BATTERY",
+            0x19 => "MBC5",
+            0x1A => "MBC5+RAM",
+            0x1B => "MBC5+RAM+BATTERY",
+            0x1C => "MBC5+RUMBLE",
+            0x1D => "MBC5+RUMBLE+RAM",
+            0x1E => "MBC5+RUMBLE+RAM+BATTERY",
+            0x20 => "MBC6",
+            0x22 => "MBC7+SENSOR+RUMBLE+RAM+BATTERY",
+            0xFC => "POCKET CAMERA",
+            0xFD => "BANDAI TAMA5",
+            0xFE => "HuC3",
+            0xFF => "HuC1+RAM+BATTERY",
+            _ => "Unknown",
+        }
+    }
+
+    /// Publisher from the new licensee code when the old code is 0x33
+    pub fn licensee_name(&self) -> &'static str {
+        if self.old_licensee_code == 0x33 {
+            new_licensee_name(&self.new_licensee_code)
+        } else {
+            old_licensee_name(self.old_licensee_code)
+        }
+    }
+}
+
+impl fmt::Display for CartridgeHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let valid = |valid: bool| if valid { "OK" } else { "BAD" };
+
+        writeln!(f, "Title:             {}", self.title)?;
+        if let Some(code) = &self.manufacturer_code {
+            writeln!(f, "Manufacturer code: {}", code)?;
+        }
+        writeln!(
+            f,
+            "CGB:               {}",
+            match self.cgb_support {
+                CgbSupport::None => "No",
+                CgbSupport::Compatible => "Supported",
+                CgbSupport::Only => "Required",
+            }
+        )?;
+        writeln!(f, "SGB:               {}", if self.sgb_support { "Supported" } else { "No" })?;
+        if self.old_licensee_code == 0x33 {
+            writeln!(f, "Licensee:          {} (new code {})", self.licensee_name(), self.new_licensee_code)?;
+        } else {
+            writeln!(f, "Licensee:          {} (old code 0x{:02X})", self.licensee_name(), self.old_licensee_code)?;
+        }
+        writeln!(f, "Cartridge type:    {} (0x{:02X})", self.cartridge_type_name(), self.cartridge_type)?;
+        match self.rom_banks() {
+            Some(banks) => writeln!(f, "ROM size:          {}KB, {} banks", banks * ROM_BANK_SIZE / 1024, banks)?,
+            None => writeln!(f, "ROM size:          Unknown (0x{:02X})", self.rom_size)?,
+        }
+        match self.ram_banks() {
+            Some(banks) => writeln!(f, "RAM size:          {}KB, {} banks", banks * RAM_BANK_SIZE / 1024, banks)?,
+            None => writeln!(f, "RAM size:          Unknown (0x{:02X})", self.ram_size)?,
+        }
+        writeln!(f, "Destination:       {}", if self.destination == 0x00 { "Japan" } else { "Overseas" })?;
+        writeln!(f, "Version:           {}", self.version)?;
+        writeln!(
+            f,
+            "Header checksum:   0x{:02X} {}",
+            self.header_checksum,
+            valid(self.header_checksum_valid())
+        )?;
+        write!(
+            f,
+            "Global checksum:   0x{:04X} {}",
+            self.global_checksum,
+            valid(self.global_checksum_valid())
+        )
+    }
+}
+
+// Printable ASCII up to the first NUL
+fn ascii(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .take_while(|c| **c != 0x00)
+        .map(|c| if c.is_ascii_graphic() || *c == b' ' { *c as char } else { '?' })
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
+fn new_licensee_name(code: &str) -> &'static str {
+    match code {
+        "00" => "None",
+        "01" => "Nintendo R&D1",
+        "08" => "Capcom",
+        "13" => "Electronic Arts",
+        "18" => "Hudson Soft",
+        "19" => "b-ai",
+        "20" => "kss",
+        "22" => "pow",
+        "24" => "PCM Complete",
+        "25" => "san-x",
+        "28" => "Kemco Japan",
+        "29" => "seta",
+        "30" => "Viacom",
+        "31" => "Nintendo",
+        "32" => "Bandai",
+        "33" => "Ocean/Acclaim",
+        "34" => "Konami",
+        "35" => "Hector",
+        "37" => "Taito",
+        "38" => "Hudson",
+        "39" => "Banpresto",
+        "41" => "Ubi Soft",
+        "42" => "Atlus",
+        "44" => "Malibu",
+        "46" => "angel",
+        "47" => "Bullet-Proof",
+        "49" => "irem",
+        "50" => "Absolute",
+        "51" => "Acclaim",
+        "52" => "Activision",
+        "53" => "American sammy",
+        "54" => "Konami",
+        "55" => "Hi tech entertainment",
+        "56" => "LJN",
+        "57" => "Matchbox",
+        "58" => "Mattel",
+        "59" => "Milton Bradley",
+        "60" => "Titus",
+        "61" => "Virgin",
+        "64" => "LucasArts",
+        "67" => "Ocean",
+        "69" => "Electronic Arts",
+        "70" => "Infogrames",
+        "71" => "Interplay",
+        "72" => "Broderbund",
+        "73" => "sculptured",
+        "75" => "sci",
+        "78" => "THQ",
+        "79" => "Accolade",
+        "80" => "misawa",
+        "83" => "lozc",
+        "86" => "Tokuma Shoten Intermedia",
+        "87" => "Tsukuda Original",
+        "91" => "Chunsoft",
+        "92" => "Video system",
+        "93" => "Ocean/Acclaim",
+        "95" => "Varie",
+        "96" => "Yonezawa/s'pal",
+        "97" => "Kaneko",
+        "99" => "Pack in soft",
+        "9H" => "Bottom Up",
+        "A4" => "Konami (Yu-Gi-Oh!)",
+        _ => "Unknown",
+    }
+}
+
+fn old_licensee_name(code: u8) -> &'static str {
+    match code {
+        0x00 => "None",
+        0x01 | 0x31 => "Nintendo",
+        0x08 | 0x38 => "Capcom",
+        0x09 => "Hot-B",
+        0x0A | 0xE0 => "Jaleco",
+        0x0B => "Coconuts",
+        0x0C | 0x6E => "Elite Systems",
+        0x13 | 0x69 => "Electronic Arts",
+        0x18 => "Hudsonsoft",
+        0x19 => "ITC Entertainment",
+        0x1A => "Yanoman",
+        0x1D => "Clary",
+        0x1F | 0x4A | 0x61 => "Virgin",
+        0x24 => "PCM Complete",
+        0x25 => "San-X",
+        0x28 => "Kotobuki Systems",
+        0x29 => "Seta",
+        0x30 | 0x70 => "Infogrames",
+        0x32 | 0xA2 | 0xB2 => "Bandai",
+        0x34 | 0xA4 => "Konami",
+        0x35 => "Hector",
+        0x39 | 0x9D => "Banpresto",
+        0x3C => "Entertainment i",
+        0x3E => "Gremlin",
+        0x41 => "Ubi Soft",
+        0x42 | 0xEB => "Atlus",
+        0x44 | 0x4D => "Malibu",
+        0x46 | 0xCF => "Angel",
+        0x47 => "Spectrum Holobyte",
+        0x49 => "Irem",
+        0x50 => "Absolute",
+        0x51 | 0xB0 => "Acclaim",
+        0x52 => "Activision",
+        0x53 => "American Sammy",
+        0x54 => "Gametek",
+        0x55 => "Park Place",
+        0x56 | 0xDB | 0xFF => "LJN",
+        0x57 => "Matchbox",
+        0x59 => "Milton Bradley",
+        0x5A => "Mindscape",
+        0x5B => "Romstar",
+        0x5C | 0xD6 => "Naxat Soft",
+        0x5D => "Tradewest",
+        0x60 => "Titus",
+        0x67 => "Ocean",
+        0x6F => "Electro Brain",
+        0x71 => "Interplay",
+        0x72 | 0xAA => "Broderbund",
+        0x73 => "Sculptered Soft",
+        0x75 => "The Sales Curve",
+        0x78 => "THQ",
+        0x79 => "Accolade",
+        0x7A => "Triffix Entertainment",
+        0x7C => "Microprose",
+        0x7F | 0xC2 => "Kemco",
+        0x80 => "Misawa Entertainment",
+        0x83 => "Lozc",
+        0x86 | 0xC4 => "Tokuma Shoten Intermedia",
+        0x8B => "Bullet-Proof Software",
+        0x8C => "Vic Tokai",
+        0x8E => "Ape",
+        0x8F => "I'Max",
+        0x91 => "Chunsoft",
+        0x92 => "Video system",
+        0x93 => "Tsuburava",
+        0x95 | 0xE3 => "Varie",
+        0x96 => "Yonezawa/s'pal",
+        0x97 => "Kaneko",
+        0x99 => "Arc",
+        0x9A => "Nihon Bussan",
+        0x9B => "Tecmo",
+        0x9C => "Imagineer",
+        0x9F => "Nova",
+        0xA1 => "Hori Electric",
+        0xA6 => "Kawada",
+        0xA7 => "Takara",
+        0xA9 => "Technos Japan",
+        0xAC => "Toei Animation",
+        0xAD => "Toho",
+        0xAF => "Namco",
+        0xB1 => "Ascii or Nexoft",
+        0xB4 => "Enix",
+        0xB6 => "HAL",
+        0xB7 => "SNK",
+        0xB9 => "Pony Canyon",
+        0xBA => "Culture Brain",
+        0xBB => "Sunsoft",
+        0xBD => "Sony Imagesoft",
+        0xBF => "Sammy",
+        0xC0 | 0xD0 => "Taito",
+        0xC3 => "Squaresoft",
+        0xC5 => "Data East",
+        0xC6 => "Tonkin House",
+        0xC8 => "Koei",
+        0xC9 => "UFL",
+        0xCA => "Ultra",
+        0xCB => "Vap",
+        0xCC => "Use",
+        0xCD => "Meldac",
+        0xCE => "Pony Canyon",
+        0xD1 => "Sofel",
+        0xD2 => "Quest",
+        0xD3 => "Sigma Enterprises",
+        0xD4 => "Ask Kodansha",
+        0xD7 => "Copya Systems",
+        0xD9 => "Banpresto",
+        0xDA => "Tomy",
+        0xDD => "NCS",
+        0xDE => "Human",
+        0xDF => "Altron",
+        0xE1 => "Towachiki",
+        0xE2 => "Uutaka",
+        0xE5 => "Epoch",
+        0xE7 => "Athena",
+        0xE8 => "Asmik",
+        0xE9 => "Natsume",
+        0xEA => "King Records",
+        0xEC => "Epic/Sony Records",
+        0xEE => "IGS",
+        0xF0 => "A Wave",
+        0xF3 => "Extreme Entertainment",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 32KB MBC1+RAM+BATTERY ROM titled TEST with both checksums filled in
+    fn rom() -> Vec<u8> {
+        let mut rom: Vec<u8> = vec![0u8; 0x8000];
+        rom[0x0134..0x0138].copy_from_slice(b"TEST");
+        rom[0x0144..0x0146].copy_from_slice(b"01");
+        rom[0x0147] = 0x03;
+        rom[0x0149] = 0x02;
+        rom[0x014B] = 0x33;
+        rom[0x0200] = 0xAB;
+        fix_checksums(&mut rom);
+        rom
+    }
+
+    fn fix_checksums(rom: &mut [u8]) {
+        rom[0x014D] = rom[0x0134..=0x014C]
+            .iter()
+            .fold(0u8, |x, b| x.wrapping_sub(*b).wrapping_sub(1));
+        rom[0x014E] = 0x00;
+        rom[0x014F] = 0x00;
+        let global: u16 = rom.iter().fold(0u16, |x, b| x.wrapping_add(*b as u16));
+        rom[0x014E] = (global >> 8) as u8;
+        rom[0x014F] = global as u8;
+    }
+
+    #[test]
+    fn parses_known_header() {
+        let header: CartridgeHeader = CartridgeHeader::parse(&rom()).unwrap();
+        assert_eq!(header.title, "TEST");
+        assert_eq!(header.cgb_support, CgbSupport::None);
+        assert_eq!(header.cartridge_type_name(), "MBC1+RAM+BATTERY");
+        assert!(header.has_battery());
+        assert_eq!(header.rom_banks(), Some(2));
+        assert_eq!(header.ram_banks(), Some(1));
+        assert_eq!(header.licensee_name(), "Nintendo R&D1");
+        assert!(header.header_checksum_valid());
+        assert!(header.global_checksum_valid());
+    }
+
+    #[test]
+    fn old_licensee_code_is_used_unless_0x33() {
+        let mut rom: Vec<u8> = rom();
+        rom[0x014B] = 0x01;
+        assert_eq!(CartridgeHeader::parse(&rom).unwrap().licensee_name(), "Nintendo");
+    }
+
+    #[test]
+    fn corrupted_header_fails_header_checksum() {
+        let mut rom: Vec<u8> = rom();
+        rom[0x0134] = b'B';
+        let header: CartridgeHeader = CartridgeHeader::parse(&rom).unwrap();
+        assert!(!header.header_checksum_valid());
+        assert!(!header.global_checksum_valid());
+    }
+
+    #[test]
+    fn corrupted_rom_fails_only_global_checksum() {
+        let mut rom: Vec<u8> = rom();
+        rom[0x0200] = 0x00;
+        let header: CartridgeHeader = CartridgeHeader::parse(&rom).unwrap();
+        assert!(header.header_checksum_valid());
+        assert!(!header.global_checksum_valid());
+    }
+
+    #[test]
+    fn short_rom_has_no_header() {
+        assert!(CartridgeHeader::parse(&rom()[..HEADER_END - 1]).is_none());
+    }
+}
@@ -0,0 +1,136 @@
+use super::*;
+
+// Up to 2MB ROM and 32KB RAM
+pub struct Mbc1 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    ram_enabled: bool,
+    bank_low: u8,  // 5 bit ROM bank 0x2000-0x3FFF
+    bank_high: u8, // 2 bit ROM bank upper bits or RAM bank 0x4000-0x5FFF
+    mode: u8,      // 0x6000-0x7FFF
+}
+
+impl Mbc1 {
+    pub fn new(rom: Vec<u8>, ram: Vec<u8>) -> Mbc1 {
+        Mbc1 {
+            rom: rom,
+            ram: ram,
+            ram_enabled: false,
+            bank_low: 1,
+            bank_high: 0,
+            mode: 0,
+        }
+    }
+
+    fn ram_addr(&self, addr: u16) -> Option<usize> {
+        // RAM banking only applies in mode 1
+        let bank: usize = if self.mode == 1 { self.bank_high as usize } else { 0 };
+        ram_offset(&self.ram, bank, addr)
+    }
+}
+
+impl Mbc for Mbc1 {
+    fn read_rom(&self, addr: u16) -> u8 {
+        let bank: usize = if addr < 0x4000 {
+            // Mode 1 also applies the upper bits to the 0x0000-0x3FFF area
+            if self.mode == 1 { (self.bank_high as usize) << 5 } else { 0 }
+        } else {
+            ((self.bank_high as usize) << 5) | self.bank_low as usize
+        };
+        self.rom[rom_offset(&self.rom, bank, addr)]
+    }
+
+    fn write_rom(&mut self, addr: u16, d8: u8) {
+        if addr < 0x2000 {
+            // RAM enabled
+            self.ram_enabled = (d8 & 0x0F) == 0x0A;
+        } else if addr < 0x4000 {
+            // ROM bank 5 lsb, 0 selects 1
+            self.bank_low = d8 & 0x1F;
+            if self.bank_low == 0x00 {
+                self.bank_low = 0x01;
+            }
+        } else if addr < 0x6000 {
+            // ROM bank 2 msb or RAM bank
+            self.bank_high = d8 & 0x03;
+        } else {
+            // ROM/RAM mode
+            self.mode = d8 & 0x01;
+        }
+    }
+
+    fn read_ram(&self, addr: u16) -> u8 {
+        if !self.ram_enabled {
+            return 0xFF;
+        }
+        self.ram_addr(addr).map(|i| self.ram[i]).unwrap_or(0xFF)
+    }
+
+    fn write_ram(&mut self, addr: u16, d8: u8) {
+        if self.ram_enabled {
+            if let Some(ram_addr) = self.ram_addr(addr) {
+                self.ram[ram_addr] = d8;
+            }
+        }
+    }
+
+    fn save_data(&self) -> Vec<u8> {
+        self.ram.clone()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        load_ram(&mut self.ram, data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bank_0_in_the_low_bits_selects_the_next_bank() {
+        let mut mbc: Mbc1 = Mbc1::new(banked_rom(64), Vec::new());
+        mbc.write_rom(0x2000, 0x00);
+        assert_eq!(mbc.read_rom(0x4000), 0x01);
+
+        // Only the low 5 bits are checked, 0x20 selects 0x21
+        mbc.write_rom(0x4000, 0x01);
+        mbc.write_rom(0x2000, 0x20);
+        assert_eq!(mbc.read_rom(0x4000), 0x21);
+    }
+
+    #[test]
+    fn mode_1_remaps_the_low_area() {
+        let mut mbc: Mbc1 = Mbc1::new(banked_rom(64), Vec::new());
+        mbc.write_rom(0x4000, 0x01);
+        assert_eq!(mbc.read_rom(0x0000), 0x00);
+
+        mbc.write_rom(0x6000, 0x01);
+        assert_eq!(mbc.read_rom(0x0000), 0x20);
+        assert_eq!(mbc.read_rom(0x4000), 0x21);
+    }
+
+    #[test]
+    fn bank_numbers_wrap_past_the_rom_size() {
+        let mut mbc: Mbc1 = Mbc1::new(banked_rom(4), Vec::new());
+        mbc.write_rom(0x2000, 0x06);
+        assert_eq!(mbc.read_rom(0x4000), 0x02);
+    }
+
+    #[test]
+    fn ram_banks_only_switch_in_mode_1() {
+        let mut mbc: Mbc1 = Mbc1::new(banked_rom(4), vec![0u8; RAM_BANK_SIZE * 4]);
+        mbc.write_ram(0xA000, 0x11);
+        assert_eq!(mbc.read_ram(0xA000), 0xFF);
+
+        mbc.write_rom(0x0000, 0x0A);
+        mbc.write_ram(0xA000, 0x11);
+        mbc.write_rom(0x4000, 0x02);
+        assert_eq!(mbc.read_ram(0xA000), 0x11);
+
+        mbc.write_rom(0x6000, 0x01);
+        mbc.write_ram(0xA000, 0x22);
+        assert_eq!(mbc.save_data()[2 * RAM_BANK_SIZE], 0x22);
+        assert_eq!(mbc.save_data()[0], 0x11);
+    }
+}
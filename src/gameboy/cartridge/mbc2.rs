@@ -0,0 +1,107 @@
+use super::*;
+
+// Up to 256KB ROM with 512 x 4bit built-in RAM
+pub struct Mbc2 {
+    rom: Vec<u8>,
+    ram: [u8; 0x200],
+    ram_enabled: bool,
+    rom_bank: u8,
+}
+
+impl Mbc2 {
+    pub fn new(rom: Vec<u8>) -> Mbc2 {
+        Mbc2 {
+            rom: rom,
+            ram: [0u8; 0x200],
+            ram_enabled: false,
+            rom_bank: 1,
+        }
+    }
+}
+
+impl Mbc for Mbc2 {
+    fn read_rom(&self, addr: u16) -> u8 {
+        let bank: usize = if addr < 0x4000 { 0 } else { self.rom_bank as usize };
+        self.rom[rom_offset(&self.rom, bank, addr)]
+    }
+
+    fn write_rom(&mut self, addr: u16, d8: u8) {
+        if addr >= 0x4000 {
+            return;
+        }
+
+        // Address bit 8 selects between RAM enable and the 4 bit ROM bank
+        if addr & 0x0100 == 0 {
+            self.ram_enabled = (d8 & 0x0F) == 0x0A;
+        } else {
+            self.rom_bank = d8 & 0x0F;
+            if self.rom_bank == 0x00 {
+                self.rom_bank = 0x01;
+            }
+        }
+    }
+
+    // The 512 bytes are mirrored through 0xA000-0xBFFF and only the low nibble exists
+    fn read_ram(&self, addr: u16) -> u8 {
+        if !self.ram_enabled {
+            return 0xFF;
+        }
+        self.ram[addr as usize & 0x01FF] | 0xF0
+    }
+
+    fn write_ram(&mut self, addr: u16, d8: u8) {
+        if self.ram_enabled {
+            self.ram[addr as usize & 0x01FF] = d8 & 0x0F;
+        }
+    }
+
+    // One byte per 4bit cell
+    fn save_data(&self) -> Vec<u8> {
+        self.ram.to_vec()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        for (cell, d8) in self.ram.iter_mut().zip(data.iter()) {
+            *cell = d8 & 0x0F;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn address_bit_8_selects_ram_enable_or_rom_bank() {
+        let mut mbc: Mbc2 = Mbc2::new(banked_rom(16));
+
+        // Bit 8 set writes the ROM bank, even with a RAM enable value
+        mbc.write_rom(0x2100, 0x0A);
+        assert_eq!(mbc.read_rom(0x4000), 0x0A);
+        assert_eq!(mbc.read_ram(0xA000), 0xFF);
+
+        // Bit 8 clear writes RAM enable and leaves the bank alone
+        mbc.write_rom(0x0000, 0x0A);
+        assert_eq!(mbc.read_rom(0x4000), 0x0A);
+        mbc.write_ram(0xA000, 0x35);
+        assert_eq!(mbc.read_ram(0xA000), 0xF5);
+    }
+
+    #[test]
+    fn bank_0_selects_bank_1_and_banks_wrap() {
+        let mut mbc: Mbc2 = Mbc2::new(banked_rom(4));
+        mbc.write_rom(0x0100, 0x00);
+        assert_eq!(mbc.read_rom(0x4000), 0x01);
+        mbc.write_rom(0x0100, 0x07);
+        assert_eq!(mbc.read_rom(0x4000), 0x03);
+    }
+
+    #[test]
+    fn ram_is_mirrored() {
+        let mut mbc: Mbc2 = Mbc2::new(banked_rom(4));
+        mbc.write_rom(0x0000, 0x0A);
+        mbc.write_ram(0xA001, 0x0C);
+        assert_eq!(mbc.read_ram(0xA201), 0xFC);
+        assert_eq!(mbc.read_ram(0xBE01), 0xFC);
+    }
+}
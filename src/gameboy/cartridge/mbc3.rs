@@ -0,0 +1,147 @@
+use super::rtc::*;
+use super::*;
+
+// Up to 2MB ROM and 32KB RAM with an optional real time clock
+pub struct Mbc3 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    rtc: Option<Rtc>,
+    ram_enabled: bool,
+    rom_bank: u8,
+    ram_bank: u8, // 0x00-0x03 RAM bank, 0x08-0x0C RTC register
+}
+
+impl Mbc3 {
+    pub fn new(rom: Vec<u8>, ram: Vec<u8>, has_rtc: bool) -> Mbc3 {
+        Mbc3 {
+            rom: rom,
+            ram: ram,
+            rtc: if has_rtc { Some(Rtc::new()) } else { None },
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+        }
+    }
+
+    fn ram_addr(&self, addr: u16) -> Option<usize> {
+        ram_offset(&self.ram, self.ram_bank as usize, addr)
+    }
+}
+
+impl Mbc for Mbc3 {
+    fn read_rom(&self, addr: u16) -> u8 {
+        let bank: usize = if addr < 0x4000 { 0 } else { self.rom_bank as usize };
+        self.rom[rom_offset(&self.rom, bank, addr)]
+    }
+
+    fn write_rom(&mut self, addr: u16, d8: u8) {
+        if addr < 0x2000 {
+            // RAM enabled
+            self.ram_enabled = (d8 & 0x0F) == 0x0A;
+        } else if addr < 0x4000 {
+            // 7 bit ROM bank, 0 selects 1
+            self.rom_bank = d8 & 0x7F;
+            if self.rom_bank == 0x00 {
+                self.rom_bank = 0x01;
+            }
+        } else if addr < 0x6000 {
+            // RAM bank or RTC register
+            self.ram_bank = d8 & 0x0F;
+        } else if let Some(rtc) = self.rtc.as_mut() {
+            // Latch clock data
+            rtc.latch(d8);
+        }
+    }
+
+    fn read_ram(&self, addr: u16) -> u8 {
+        if !self.ram_enabled {
+            return 0xFF;
+        }
+
+        match (self.ram_bank, self.rtc.as_ref()) {
+            (0x00..=0x03, _) => self.ram_addr(addr).map(|i| self.ram[i]).unwrap_or(0xFF),
+            (0x08..=0x0C, Some(rtc)) => rtc.read(self.ram_bank),
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, addr: u16, d8: u8) {
+        if !self.ram_enabled {
+            return;
+        }
+
+        match self.ram_bank {
+            0x00..=0x03 => {
+                if let Some(ram_addr) = self.ram_addr(addr) {
+                    self.ram[ram_addr] = d8;
+                }
+            }
+            0x08..=0x0C => {
+                if let Some(rtc) = self.rtc.as_mut() {
+                    rtc.write(self.ram_bank, d8);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        if let Some(rtc) = self.rtc.as_mut() {
+            rtc.tick(cycles);
+        }
+    }
+
+    // RAM followed by the RTC trailer
+    fn save_data(&self) -> Vec<u8> {
+        let mut data: Vec<u8> = self.ram.clone();
+        if let Some(rtc) = self.rtc.as_ref() {
+            data.extend(rtc.save_data());
+        }
+        data
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        let ram_size: usize = load_ram(&mut self.ram, data);
+
+        if let Some(rtc) = self.rtc.as_mut() {
+            rtc.load_save_data(&data[ram_size..]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seven_bit_rom_bank_with_bank_0_selecting_1() {
+        let mut mbc: Mbc3 = Mbc3::new(banked_rom(128), Vec::new(), false);
+        mbc.write_rom(0x2000, 0x00);
+        assert_eq!(mbc.read_rom(0x4000), 0x01);
+        mbc.write_rom(0x2000, 0xFF);
+        assert_eq!(mbc.read_rom(0x4000), 0x7F);
+    }
+
+    #[test]
+    fn ram_bank_register_maps_the_clock() {
+        let mut mbc: Mbc3 = Mbc3::new(banked_rom(4), vec![0u8; RAM_BANK_SIZE], true);
+        mbc.write_rom(0x0000, 0x0A);
+        mbc.write_ram(0xA000, 0x55);
+
+        mbc.write_rom(0x4000, 0x09);
+        mbc.write_ram(0xA000, 0x12);
+        assert_eq!(mbc.read_ram(0xA000), 0x12);
+
+        mbc.write_rom(0x4000, 0x00);
+        assert_eq!(mbc.read_ram(0xA000), 0x55);
+    }
+
+    #[test]
+    fn save_data_round_trips_ram() {
+        let mut mbc: Mbc3 = Mbc3::new(banked_rom(4), vec![0u8; RAM_BANK_SIZE], false);
+        let mut data: Vec<u8> = vec![0u8; RAM_BANK_SIZE];
+        data[0x10] = 0x42;
+        mbc.load_save_data(&data);
+        assert_eq!(mbc.save_data(), data);
+    }
+}
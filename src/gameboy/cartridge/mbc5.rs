@@ -0,0 +1,108 @@
+use super::*;
+
+// Up to 8MB ROM and 128KB RAM
+pub struct Mbc5 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    ram_enabled: bool,
+    rom_bank: u16,
+    ram_bank: u8,
+}
+
+impl Mbc5 {
+    pub fn new(rom: Vec<u8>, ram: Vec<u8>) -> Mbc5 {
+        Mbc5 {
+            rom: rom,
+            ram: ram,
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+        }
+    }
+
+    fn ram_addr(&self, addr: u16) -> Option<usize> {
+        ram_offset(&self.ram, self.ram_bank as usize, addr)
+    }
+}
+
+impl Mbc for Mbc5 {
+    fn read_rom(&self, addr: u16) -> u8 {
+        let bank: usize = if addr < 0x4000 { 0 } else { self.rom_bank as usize };
+        self.rom[rom_offset(&self.rom, bank, addr)]
+    }
+
+    fn write_rom(&mut self, addr: u16, d8: u8) {
+        if addr < 0x2000 {
+            // RAM enabled
+            self.ram_enabled = (d8 & 0x0F) == 0x0A;
+        } else if addr < 0x3000 {
+            // ROM bank 8 lsb, unlike MBC1/3 bank 0 can be selected
+            self.rom_bank = (self.rom_bank & 0x100) | d8 as u16;
+        } else if addr < 0x4000 {
+            // ROM bank bit 9
+            self.rom_bank = (self.rom_bank & 0xFF) | ((d8 as u16 & 0x01) << 8);
+        } else if addr < 0x6000 {
+            // RAM bank
+            self.ram_bank = d8 & 0x0F;
+        }
+    }
+
+    fn read_ram(&self, addr: u16) -> u8 {
+        if !self.ram_enabled {
+            return 0xFF;
+        }
+        self.ram_addr(addr).map(|i| self.ram[i]).unwrap_or(0xFF)
+    }
+
+    fn write_ram(&mut self, addr: u16, d8: u8) {
+        if self.ram_enabled {
+            if let Some(ram_addr) = self.ram_addr(addr) {
+                self.ram[ram_addr] = d8;
+            }
+        }
+    }
+
+    fn save_data(&self) -> Vec<u8> {
+        self.ram.clone()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        load_ram(&mut self.ram, data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bank_0_is_selectable() {
+        let mut mbc: Mbc5 = Mbc5::new(banked_rom(4), Vec::new());
+        assert_eq!(mbc.read_rom(0x4000), 0x01);
+        mbc.write_rom(0x2000, 0x00);
+        assert_eq!(mbc.read_rom(0x4000), 0x00);
+    }
+
+    #[test]
+    fn ninth_bank_bit_is_written_through_0x3000() {
+        let mut mbc: Mbc5 = Mbc5::new(banked_rom(0x102), Vec::new());
+        mbc.write_rom(0x2000, 0x01);
+        mbc.write_rom(0x3000, 0x01);
+        assert_eq!(mbc.read_rom(0x4000), 0x01);
+        assert_eq!(mbc.read_rom(0x4001), 0x01);
+
+        // Writing the low bits keeps bit 9
+        mbc.write_rom(0x2000, 0x00);
+        assert_eq!(mbc.read_rom(0x4000), 0x00);
+        assert_eq!(mbc.read_rom(0x4001), 0x01);
+    }
+
+    #[test]
+    fn bank_numbers_wrap_past_the_rom_size() {
+        let mut mbc: Mbc5 = Mbc5::new(banked_rom(4), Vec::new());
+        mbc.write_rom(0x2000, 0x07);
+        mbc.write_rom(0x3000, 0x01);
+        assert_eq!(mbc.read_rom(0x4000), 0x03);
+        assert_eq!(mbc.read_rom(0x4001), 0x00);
+    }
+}
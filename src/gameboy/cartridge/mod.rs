@@ -0,0 +1,174 @@
+mod header;
+mod mbc1;
+mod mbc2;
+mod mbc3;
+mod mbc5;
+mod rom_only;
+mod rtc;
+
+use super::error::*;
+pub use header::*;
+use mbc1::*;
+use mbc2::*;
+use mbc3::*;
+use mbc5::*;
+use rom_only::*;
+
+pub const ROM_BANK_SIZE: usize = 0x4000;
+pub const RAM_BANK_SIZE: usize = 0x2000;
+
+// Offset into the ROM of addr in the given bank, bank numbers past the
+// end of the ROM wrap around like the unconnected address lines do
+pub fn rom_offset(rom: &[u8], bank: usize, addr: u16) -> usize {
+    let banks: usize = (rom.len() / ROM_BANK_SIZE).max(1);
+    (bank % banks) * ROM_BANK_SIZE + (addr as usize & 0x3FFF)
+}
+
+// Offset into the RAM of addr in the given bank, None if the cart has no RAM
+pub fn ram_offset(ram: &[u8], bank: usize, addr: u16) -> Option<usize> {
+    if ram.is_empty() {
+        return None;
+    }
+    let banks: usize = (ram.len() / RAM_BANK_SIZE).max(1);
+    Some(((bank % banks) * RAM_BANK_SIZE + (addr as usize & 0x1FFF)) % ram.len())
+}
+
+// Fill the RAM from the start of a .sav file, returns the number of bytes used
+pub fn load_ram(ram: &mut [u8], data: &[u8]) -> usize {
+    let ram_size: usize = ram.len().min(data.len());
+    ram[..ram_size].copy_from_slice(&data[..ram_size]);
+    ram_size
+}
+
+// ROM of the given number of banks, each starting with its bank number as a little endian u16
+#[cfg(test)]
+pub fn banked_rom(banks: usize) -> Vec<u8> {
+    let mut rom: Vec<u8> = vec![0u8; banks * ROM_BANK_SIZE];
+    for bank in 0..banks {
+        rom[bank * ROM_BANK_SIZE] = bank as u8;
+        rom[bank * ROM_BANK_SIZE + 1] = (bank >> 8) as u8;
+    }
+    rom
+}
+
+// Memory bank controller, maps the cartridge ROM and RAM into 0x0000-0x7FFF and 0xA000-0xBFFF
+pub trait Mbc: Send {
+    // Read 0x0000-0x7FFF
+    fn read_rom(&self, addr: u16) -> u8;
+    // Write to the control registers mapped over 0x0000-0x7FFF
+    fn write_rom(&mut self, addr: u16, d8: u8);
+    // Read 0xA000-0xBFFF
+    fn read_ram(&self, addr: u16) -> u8;
+    // Write 0xA000-0xBFFF
+    fn write_ram(&mut self, addr: u16, d8: u8);
+
+    // Advance cartridge hardware such as a real time clock by M-cycles
+    fn tick(&mut self, _cycles: u32) {}
+
+    // Battery backed state in the .sav layout used by other emulators
+    fn save_data(&self) -> Vec<u8> {
+        Vec::new()
+    }
+    fn load_save_data(&mut self, _data: &[u8]) {}
+}
+
+pub struct Cartridge {
+    mbc: Box<dyn Mbc>,
+    has_battery: bool,
+}
+
+impl Cartridge {
+    // Cartridge slot with nothing inserted
+    pub fn empty() -> Cartridge {
+        Cartridge {
+            mbc: Box::new(RomOnly::new(Vec::new(), Vec::new())),
+            has_battery: false,
+        }
+    }
+
+    // Build the cartridge described by the ROM header
+    pub fn new(rom: Vec<u8>) -> Result<Cartridge, EmulatorError> {
+        let header: CartridgeHeader = match CartridgeHeader::parse(&rom) {
+            Some(header) => header,
+            None => {
+                return Err(EmulatorError::TruncatedRom {
+                    expected: HEADER_END,
+                    actual: rom.len(),
+                })
+            }
+        };
+
+        let rom_banks: usize = match header.rom_banks() {
+            Some(banks) => banks,
+            None => {
+                return Err(EmulatorError::MalformedHeader(format!(
+                    "unknown ROM size 0x{:02X}",
+                    header.rom_size
+                )))
+            }
+        };
+
+        let ram_banks: usize = match header.ram_banks() {
+            Some(banks) => banks,
+            None => {
+                return Err(EmulatorError::MalformedHeader(format!(
+                    "unknown RAM size 0x{:02X}",
+                    header.ram_size
+                )))
+            }
+        };
+
+        if rom.len() < rom_banks * ROM_BANK_SIZE {
+            return Err(EmulatorError::TruncatedRom {
+                expected: rom_banks * ROM_BANK_SIZE,
+                actual: rom.len(),
+            });
+        }
+
+        let ram: Vec<u8> = vec![0u8; ram_banks * RAM_BANK_SIZE];
+
+        let mbc: Box<dyn Mbc> = match header.cartridge_type {
+            0x00 | 0x08 | 0x09 => Box::new(RomOnly::new(rom, ram)),
+            0x01..=0x03 => Box::new(Mbc1::new(rom, ram)),
+            0x05 | 0x06 => Box::new(Mbc2::new(rom)),
+            0x0F..=0x13 => Box::new(Mbc3::new(rom, ram, header.has_rtc())),
+            0x19..=0x1E => Box::new(Mbc5::new(rom, ram)),
+            _ => return Err(EmulatorError::UnsupportedMapper(header.cartridge_type)),
+        };
+
+        Ok(Cartridge {
+            mbc: mbc,
+            has_battery: header.has_battery(),
+        })
+    }
+
+    pub fn read_rom(&self, addr: u16) -> u8 {
+        self.mbc.read_rom(addr)
+    }
+    pub fn write_rom(&mut self, addr: u16, d8: u8) {
+        self.mbc.write_rom(addr, d8);
+    }
+
+    pub fn read_ram(&self, addr: u16) -> u8 {
+        self.mbc.read_ram(addr)
+    }
+    pub fn write_ram(&mut self, addr: u16, d8: u8) {
+        self.mbc.write_ram(addr, d8);
+    }
+
+    pub fn tick(&mut self, cycles: u32) {
+        self.mbc.tick(cycles);
+    }
+
+    // RAM and clock state survive power off only with a battery
+    pub fn has_battery(&self) -> bool {
+        self.has_battery
+    }
+
+    pub fn save_data(&self) -> Vec<u8> {
+        self.mbc.save_data()
+    }
+    pub fn load_save_data(&mut self, data: &[u8]) {
+        self.mbc.load_save_data(data);
+    }
+}
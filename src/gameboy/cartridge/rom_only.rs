@@ -0,0 +1,39 @@
+use super::load_ram;
+
+// 32KB ROM with optional 8KB RAM and no banking
+pub struct RomOnly {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+}
+
+impl RomOnly {
+    pub fn new(rom: Vec<u8>, ram: Vec<u8>) -> RomOnly {
+        RomOnly { rom: rom, ram: ram }
+    }
+}
+
+impl super::Mbc for RomOnly {
+    fn read_rom(&self, addr: u16) -> u8 {
+        self.rom.get(addr as usize).copied().unwrap_or(0xFF)
+    }
+
+    fn write_rom(&mut self, _addr: u16, _d8: u8) {}
+
+    fn read_ram(&self, addr: u16) -> u8 {
+        self.ram.get(addr as usize - 0xA000).copied().unwrap_or(0xFF)
+    }
+
+    fn write_ram(&mut self, addr: u16, d8: u8) {
+        if let Some(byte) = self.ram.get_mut(addr as usize - 0xA000) {
+            *byte = d8;
+        }
+    }
+
+    fn save_data(&self) -> Vec<u8> {
+        self.ram.clone()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        load_ram(&mut self.ram, data);
+    }
+}
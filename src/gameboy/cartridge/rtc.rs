@@ -0,0 +1,204 @@
+use std::convert::TryInto;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// M-cycles in one second of emulated time
+const CYCLES_PER_SECOND: u32 = 1_048_576;
+
+// Size of the RTC trailer appended to the RAM in .sav files
+pub const RTC_SAVE_SIZE: usize = 48;
+
+// DH bits
+const DAY_HIGH: u8 = 0x01;
+const HALT: u8 = 0x40;
+const DAY_CARRY: u8 = 0x80;
+
+// MBC3 real time clock registers 0x08-0x0C
+#[derive(Copy, Clone, Default)]
+struct RtcRegisters {
+    seconds: u8,
+    minutes: u8,
+    hours: u8,
+    day_low: u8,
+    day_high: u8,
+}
+
+impl RtcRegisters {
+    fn get(&self, reg: u8) -> u8 {
+        match reg {
+            0x08 => self.seconds,
+            0x09 => self.minutes,
+            0x0A => self.hours,
+            0x0B => self.day_low,
+            _ => self.day_high,
+        }
+    }
+
+    fn to_bytes(self) -> [u8; 5] {
+        [self.seconds, self.minutes, self.hours, self.day_low, self.day_high]
+    }
+}
+
+pub struct Rtc {
+    live: RtcRegisters,
+    latched: RtcRegisters,
+    latch_write: u8,
+    cycles: u32,
+}
+
+impl Rtc {
+    pub fn new() -> Rtc {
+        Rtc {
+            live: RtcRegisters::default(),
+            latched: RtcRegisters::default(),
+            latch_write: 0xFF,
+            cycles: 0,
+        }
+    }
+
+    // Reads see the registers as of the last latch
+    pub fn read(&self, reg: u8) -> u8 {
+        self.latched.get(reg)
+    }
+
+    pub fn write(&mut self, reg: u8, d8: u8) {
+        match reg {
+            0x08 => {
+                // Writing the seconds restarts the current second
+                self.live.seconds = d8 & 0x3F;
+                self.cycles = 0;
+            }
+            0x09 => self.live.minutes = d8 & 0x3F,
+            0x0A => self.live.hours = d8 & 0x1F,
+            0x0B => self.live.day_low = d8,
+            _ => self.live.day_high = d8 & (DAY_HIGH | HALT | DAY_CARRY),
+        }
+        self.latched = self.live;
+    }
+
+    // Writing 0x00 then 0x01 to 0x6000-0x7FFF copies the clock into the latched registers
+    pub fn latch(&mut self, d8: u8) {
+        if self.latch_write == 0x00 && d8 == 0x01 {
+            self.latched = self.live;
+        }
+        self.latch_write = d8;
+    }
+
+    // Advance by M-cycles of emulated time
+    pub fn tick(&mut self, cycles: u32) {
+        if self.live.day_high & HALT == HALT {
+            return;
+        }
+
+        self.cycles += cycles;
+        while self.cycles >= CYCLES_PER_SECOND {
+            self.cycles -= CYCLES_PER_SECOND;
+            self.advance(1);
+        }
+    }
+
+    // Advance the running clock by whole seconds
+    fn advance(&mut self, seconds: u64) {
+        if self.live.day_high & HALT == HALT || seconds == 0 {
+            return;
+        }
+
+        let seconds: u64 = self.live.seconds as u64 + seconds;
+        self.live.seconds = (seconds % 60) as u8;
+        let mut carry: u64 = seconds / 60;
+
+        let minutes: u64 = self.live.minutes as u64 + carry;
+        self.live.minutes = (minutes % 60) as u8;
+        carry = minutes / 60;
+
+        let hours: u64 = self.live.hours as u64 + carry;
+        self.live.hours = (hours % 24) as u8;
+        carry = hours / 24;
+
+        let days: u64 = (((self.live.day_high & DAY_HIGH) as u64) << 8 | self.live.day_low as u64) + carry;
+        if days > 0x1FF {
+            self.live.day_high |= DAY_CARRY;
+        }
+        self.live.day_low = days as u8;
+        self.live.day_high = (self.live.day_high & !DAY_HIGH) | ((days >> 8) as u8 & DAY_HIGH);
+    }
+
+    // 48 byte trailer used by other emulators: live and latched registers as
+    // little endian u32s followed by a u64 unix timestamp
+    pub fn save_data(&self) -> Vec<u8> {
+        let mut data: Vec<u8> = Vec::with_capacity(RTC_SAVE_SIZE);
+        for reg in self.live.to_bytes().iter().chain(self.latched.to_bytes().iter()) {
+            data.extend_from_slice(&(*reg as u32).to_le_bytes());
+        }
+        data.extend_from_slice(&unix_time().to_le_bytes());
+        data
+    }
+
+    // Restore from a save trailer, catching up on the time since it was written
+    pub fn load_save_data(&mut self, data: &[u8]) {
+        if data.len() < RTC_SAVE_SIZE - 4 {
+            return;
+        }
+
+        let reg = |i: usize| data[i * 4];
+        self.live = RtcRegisters {
+            seconds: reg(0),
+            minutes: reg(1),
+            hours: reg(2),
+            day_low: reg(3),
+            day_high: reg(4),
+        };
+        self.latched = RtcRegisters {
+            seconds: reg(5),
+            minutes: reg(6),
+            hours: reg(7),
+            day_low: reg(8),
+            day_high: reg(9),
+        };
+
+        // Some emulators write a 32 bit timestamp for a 44 byte trailer
+        let saved_at: u64 = if data.len() >= RTC_SAVE_SIZE {
+            u64::from_le_bytes(data[40..48].try_into().unwrap())
+        } else {
+            u32::from_le_bytes(data[40..44].try_into().unwrap()) as u64
+        };
+        self.advance(unix_time().saturating_sub(saved_at));
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn latched(rtc: &mut Rtc) -> [u8; 5] {
+        rtc.latch(0x00);
+        rtc.latch(0x01);
+        [rtc.read(0x08), rtc.read(0x09), rtc.read(0x0A), rtc.read(0x0B), rtc.read(0x0C)]
+    }
+
+    #[test]
+    fn day_counter_overflow_sets_carry() {
+        // Day 511, 23:59:59
+        let mut rtc: Rtc = Rtc::new();
+        rtc.write(0x08, 59);
+        rtc.write(0x09, 59);
+        rtc.write(0x0A, 23);
+        rtc.write(0x0B, 0xFF);
+        rtc.write(0x0C, DAY_HIGH);
+
+        rtc.tick(CYCLES_PER_SECOND);
+        assert_eq!(latched(&mut rtc), [0, 0, 0, 0x00, DAY_CARRY]);
+    }
+
+    #[test]
+    fn halted_clock_does_not_advance() {
+        let mut rtc: Rtc = Rtc::new();
+        rtc.write(0x0C, HALT);
+
+        rtc.tick(CYCLES_PER_SECOND * 2);
+        assert_eq!(latched(&mut rtc), [0, 0, 0, 0x00, HALT]);
+    }
+}
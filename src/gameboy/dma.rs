@@ -0,0 +1,71 @@
+// Bytes copied into OAM by one transfer, one per M-cycle
+const TRANSFER_LENGTH: u16 = 0xA0;
+
+// OAM DMA started by writing the source page to 0xFF46
+pub struct Dma {
+    source: u8,
+    // Source written by the instruction that is running
+    pending: Option<u8>,
+    progress: u16,
+    active: bool,
+    starting: bool,
+}
+
+impl Dma {
+    pub fn new() -> Dma {
+        Dma {
+            source: 0xFF,
+            pending: None,
+            progress: 0,
+            active: false,
+            starting: false,
+        }
+    }
+
+    pub fn read(&self) -> u8 {
+        self.pending.unwrap_or(self.source)
+    }
+
+    // Request a transfer from d8 * 0x100, it starts once the writing instruction is done
+    pub fn write(&mut self, d8: u8) {
+        self.pending = Some(d8);
+    }
+
+    // The write lands on the last M-cycle of the instruction, so a transfer
+    // (or restart) requested by it only counts its cycles from here
+    pub fn start_pending(&mut self) {
+        if let Some(source) = self.pending.take() {
+            self.source = source;
+            self.progress = 0;
+            self.starting = true;
+        }
+    }
+
+    // While a transfer runs the CPU can only reach HRAM and IO
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    // Advance one M-cycle, returns the (source, destination) of the byte to copy
+    pub fn tick(&mut self) -> Option<(u16, u16)> {
+        // The transfer begins one M-cycle after the write
+        if self.starting {
+            self.starting = false;
+            self.active = true;
+            return None;
+        }
+
+        if !self.active {
+            return None;
+        }
+
+        let source: u16 = ((self.source as u16) << 8) | self.progress;
+        let destination: u16 = 0xFE00 | self.progress;
+
+        self.progress += 1;
+        if self.progress == TRANSFER_LENGTH {
+            self.active = false;
+        }
+        Some((source, destination))
+    }
+}
@@ -0,0 +1,57 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// Everything that can go wrong while loading or running a ROM
+#[derive(Debug)]
+pub enum EmulatorError {
+    /// Reading a ROM or reading/writing a save file failed
+    Io(io::Error),
+    /// A header field holds a value no cartridge uses
+    MalformedHeader(String),
+    /// The ROM is shorter than its header (or the header itself) says
+    TruncatedRom { expected: usize, actual: usize },
+    /// The cartridge type names a memory bank controller we don't emulate
+    UnsupportedMapper(u8),
+    /// The CPU fetched one of the 11 undefined opcodes and locked up
+    IllegalOpcode { opcode: u8, addr: u16 },
+    /// A palette or bindings config file could not be parsed
+    Config(String),
+    /// The window, renderer or input backend failed
+    Frontend(String),
+}
+
+impl fmt::Display for EmulatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmulatorError::Io(e) => write!(f, "I/O error: {}", e),
+            EmulatorError::MalformedHeader(message) => write!(f, "Malformed cartridge header: {}", message),
+            EmulatorError::TruncatedRom { expected, actual } => {
+                write!(f, "Truncated ROM: expected {} bytes, found {}", expected, actual)
+            }
+            EmulatorError::UnsupportedMapper(cartridge_type) => {
+                write!(f, "Unsupported cartridge type 0x{:02X}", cartridge_type)
+            }
+            EmulatorError::IllegalOpcode { opcode, addr } => {
+                write!(f, "Illegal opcode 0x{:02X} at 0x{:04X}, CPU locked", opcode, addr)
+            }
+            EmulatorError::Config(message) => write!(f, "{}", message),
+            EmulatorError::Frontend(message) => write!(f, "Frontend error: {}", message),
+        }
+    }
+}
+
+impl Error for EmulatorError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            EmulatorError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for EmulatorError {
+    fn from(e: io::Error) -> EmulatorError {
+        EmulatorError::Io(e)
+    }
+}
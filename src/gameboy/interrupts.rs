@@ -0,0 +1,111 @@
+// Interrupt sources, value = bit in IE/IF and priority (lowest first)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Interrupt {
+    VBlank = 0,
+    Stat = 1,
+    Timer = 2,
+    Serial = 3,
+    Joypad = 4,
+}
+
+impl Interrupt {
+    // Address the CPU jumps to when servicing the interrupt
+    pub fn vector(&self) -> u16 {
+        0x0040 + (*self as u16) * 0x08
+    }
+}
+
+const INTERRUPTS: [Interrupt; 5] = [
+    Interrupt::VBlank,
+    Interrupt::Stat,
+    Interrupt::Timer,
+    Interrupt::Serial,
+    Interrupt::Joypad,
+];
+
+// M-cycles taken to push PC and jump to the vector
+pub const DISPATCH_CYCLES: u32 = 5;
+
+pub struct Interrupts {
+    ime: bool,
+    ei_delay: u8,
+    enabled: u8,   // IE 0xFFFF
+    requested: u8, // IF 0xFF0F
+}
+
+impl Interrupts {
+    pub fn new() -> Interrupts {
+        Interrupts {
+            ime: false,
+            ei_delay: 0,
+            enabled: 0x00,
+            requested: 0xE1,
+        }
+    }
+
+    // IE
+    pub fn get_ie(&self) -> u8 {
+        self.enabled
+    }
+    pub fn set_ie(&mut self, ie: u8) {
+        self.enabled = ie;
+    }
+
+    // IF, the upper 3 bits are unused and read as 1
+    pub fn get_if(&self) -> u8 {
+        self.requested | 0xE0
+    }
+    pub fn set_if(&mut self, flags: u8) {
+        self.requested = flags & 0x1F;
+    }
+
+    pub fn get_ime(&self) -> bool {
+        self.ime
+    }
+
+    // Set IME immediately (DI, RETI)
+    pub fn set_ime(&mut self, ime: bool) {
+        self.ime = ime;
+        self.ei_delay = 0;
+    }
+
+    // EI only sets IME after the following instruction has executed
+    pub fn enable_delayed(&mut self) {
+        if !self.ime {
+            self.ei_delay = 2;
+        }
+    }
+
+    // Called once after every instruction to apply a pending EI
+    pub fn update_ime(&mut self) {
+        if self.ei_delay > 0 {
+            self.ei_delay -= 1;
+            if self.ei_delay == 0 {
+                self.ime = true;
+            }
+        }
+    }
+
+    // Raise the IF bit for an interrupt
+    pub fn request(&mut self, interrupt: Interrupt) {
+        self.requested |= 0x01 << interrupt as u8;
+    }
+
+    // Interrupts both requested and enabled, regardless of IME
+    pub fn pending(&self) -> u8 {
+        self.enabled & self.requested & 0x1F
+    }
+
+    // Take the highest priority pending interrupt if IME allows it, clearing its IF bit and IME
+    pub fn acknowledge(&mut self) -> Option<Interrupt> {
+        if !self.ime {
+            return None;
+        }
+
+        let pending: u8 = self.pending();
+        let interrupt = INTERRUPTS.iter().find(|i| (pending >> **i as u8) & 0x01 == 0x01)?;
+        self.requested &= !(0x01 << *interrupt as u8);
+        self.ime = false;
+        Some(*interrupt)
+    }
+}
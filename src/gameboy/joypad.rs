@@ -0,0 +1,130 @@
+/// The eight buttons, value = bit in the mask passed to `Gameboy::set_buttons`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Button {
+    Right = 0,
+    Left = 1,
+    Up = 2,
+    Down = 3,
+    A = 4,
+    B = 5,
+    Select = 6,
+    Start = 7,
+}
+
+impl Button {
+    /// Button by its lowercase name, e.g. "start"
+    pub fn from_name(name: &str) -> Option<Button> {
+        match name.to_lowercase().as_str() {
+            "right" => Some(Button::Right),
+            "left" => Some(Button::Left),
+            "up" => Some(Button::Up),
+            "down" => Some(Button::Down),
+            "a" => Some(Button::A),
+            "b" => Some(Button::B),
+            "select" => Some(Button::Select),
+            "start" => Some(Button::Start),
+            _ => None,
+        }
+    }
+
+    /// Bit of this button in the mask passed to `Gameboy::set_buttons`
+    pub fn mask(&self) -> u8 {
+        0x01 << *self as u8
+    }
+}
+
+// P1 0xFF00, buttons are read through two select lines and are active low
+pub struct Joypad {
+    select: u8,
+    pressed: u8,
+}
+
+impl Joypad {
+    pub fn new() -> Joypad {
+        Joypad {
+            select: 0x30,
+            pressed: 0x00,
+        }
+    }
+
+    pub fn read(&self) -> u8 {
+        0xC0 | self.select | self.lines()
+    }
+
+    // Returns true if the joypad interrupt should be requested because the
+    // new select lines pull a held button's line low
+    pub fn write(&mut self, d8: u8) -> bool {
+        let before: u8 = self.lines();
+        self.select = d8 & 0x30;
+        before & !self.lines() != 0
+    }
+
+    // Replace the pressed button mask, returns true if the joypad interrupt
+    // should be requested because a selected line went low
+    pub fn set_buttons(&mut self, pressed: u8) -> bool {
+        let before: u8 = self.lines();
+        self.pressed = pressed;
+        before & !self.lines() != 0
+    }
+
+    // Low nibble of P1 for the selected lines, 0 = pressed
+    pub fn lines(&self) -> u8 {
+        let mut lines: u8 = 0x0F;
+        // Bit 4 low selects the d-pad
+        if self.select & 0x10 == 0 {
+            lines &= !(self.pressed & 0x0F);
+        }
+        // Bit 5 low selects the action buttons
+        if self.select & 0x20 == 0 {
+            lines &= !(self.pressed >> 4);
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DPAD: u8 = 0x20;
+    const ACTION: u8 = 0x10;
+
+    #[test]
+    fn select_lines_choose_the_d_pad_or_action_buttons() {
+        let mut joypad: Joypad = Joypad::new();
+        joypad.set_buttons(Button::Right.mask() | Button::Start.mask());
+        assert_eq!(joypad.read(), 0xFF);
+
+        joypad.write(DPAD);
+        assert_eq!(joypad.read(), 0xEE);
+        joypad.write(ACTION);
+        assert_eq!(joypad.read(), 0xD7);
+
+        // Both selected ANDs the two groups
+        joypad.write(0x00);
+        assert_eq!(joypad.read(), 0xC6);
+    }
+
+    #[test]
+    fn pressing_a_selected_button_requests_the_interrupt() {
+        let mut joypad: Joypad = Joypad::new();
+        joypad.write(DPAD);
+        assert!(!joypad.set_buttons(Button::A.mask()));
+        assert!(joypad.set_buttons(Button::A.mask() | Button::Up.mask()));
+        // Holding or releasing doesn't
+        assert!(!joypad.set_buttons(Button::A.mask() | Button::Up.mask()));
+        assert!(!joypad.set_buttons(0x00));
+    }
+
+    #[test]
+    fn selecting_a_held_button_requests_the_interrupt() {
+        let mut joypad: Joypad = Joypad::new();
+        joypad.write(DPAD);
+        joypad.set_buttons(Button::B.mask());
+
+        assert!(joypad.write(ACTION));
+        assert!(!joypad.write(ACTION));
+        assert!(!joypad.write(DPAD));
+        assert!(!joypad.write(0x30));
+    }
+}
@@ -1,226 +1,480 @@
-use std::fs::File;
-use std::io::Read;
-
+use std::fs;
+use std::path::{Path, PathBuf};
+
+mod apu;
+mod cartridge;
+mod cycles;
+mod dma;
+mod error;
+mod interrupts;
+mod joypad;
+mod ppu;
 mod registers;
-mod window;
-
+mod serial;
+mod timer;
+
+pub use apu::SAMPLE_RATE;
+use apu::*;
+pub use cartridge::{CartridgeHeader, CgbSupport};
+pub use error::EmulatorError;
+use cartridge::*;
+use cycles::*;
+use dma::*;
+use interrupts::*;
+pub use joypad::Button;
+use joypad::*;
+pub use ppu::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use ppu::*;
 use registers::*;
-use window::*;
+use serial::*;
+use timer::*;
+
+// M-cycles per scanline and per full frame of 154 lines
+const CYCLES_PER_LINE: u32 = 114;
+const CYCLES_PER_FRAME: u32 = CYCLES_PER_LINE * 154;
+
+// 8bit operand of an instruction, either a register or the byte at (HL)
+#[derive(Copy, Clone)]
+pub enum Target {
+    Reg(Reg8),
+    HL,
+}
 
+impl Target {
+    // Decode the operand encoded in the low 3 bits of an opcode
+    pub fn from_bits(bits: u8) -> Target {
+        match bits & 0x07 {
+            0 => Target::Reg(Reg8::B),
+            1 => Target::Reg(Reg8::C),
+            2 => Target::Reg(Reg8::D),
+            3 => Target::Reg(Reg8::E),
+            4 => Target::Reg(Reg8::H),
+            5 => Target::Reg(Reg8::L),
+            6 => Target::HL,
+            _ => Target::Reg(Reg8::A),
+        }
+    }
+}
+
+/// A DMG Game Boy: CPU, memory bus, PPU, timer, serial port, joypad and cartridge.
 pub struct Gameboy {
     memory: [u8; 0x10000],
     registers: Registers,
-    window: SdlWindow,
-    rom_banks: Vec<[u8; 0x4000]>,
-    ram_banks: Vec<[u8; 0x2000]>,
-    selected_rom_bank: usize,
-    selected_ram_bank: usize,
-    ram_enabled: bool,
-    rxm_mode: u8,
-    //mbc: i8, // 0 for HuC1
+    interrupts: Interrupts,
+    serial: Serial,
+    timer: Timer,
+    ppu: Ppu,
+    apu: Apu,
+    dma: Dma,
+    joypad: Joypad,
+    cartridge: Cartridge,
+    boot_rom: Option<Vec<u8>>,
+    save_dir: Option<PathBuf>,
+    save_path: Option<PathBuf>,
+    saved_data: Vec<u8>,
+    frames: u32,
+    cycles: u64,
+    locked: bool,
+    halted: bool,
+    halt_bug: bool,
+    stopped: bool,
+    branch_taken: bool,
+    frame_cycles: u32,
+}
+
+impl Default for Gameboy {
+    fn default() -> Gameboy {
+        Gameboy::new()
+    }
 }
 
 impl Gameboy {
-    pub fn new() -> Result<Gameboy, String> {
-        Ok(Gameboy {
+    /// Power on with an empty cartridge slot.
+    ///
+    /// The core has no display, input or audio device of its own, a frontend
+    /// presents [`frame_buffer`](Gameboy::frame_buffer), feeds
+    /// [`set_buttons`](Gameboy::set_buttons) and plays
+    /// [`audio_samples`](Gameboy::audio_samples).
+    pub fn new() -> Gameboy {
+        Gameboy {
             memory: [0u8; 0x10000],
             registers: Registers::new(),
-            window: SdlWindow::new()?,
-            rom_banks: Vec::new(),
-            ram_banks: Vec::new(),
-            selected_rom_bank: 1,
-            selected_ram_bank: 0,
-            ram_enabled: false,
-            rxm_mode: 0,
-            //mbc: -1,
-        })
-    }
-
-    pub fn load_rom(&mut self, rom_name: String) -> Result<(), std::io::Error> {
-        // Only works for 32k roms
-
-        let mut rom_file = File::open(rom_name)?;
-        let mut bytes = Vec::new();
-        rom_file.read_to_end(&mut bytes)?;
-
-        let _mbc_type = bytes[0x0147];
-        let rom_size = bytes[0x0148];
-        let ram_size = bytes[0x0149];
-
-        match rom_size {
-            0x00 => {
-                for _ in 0..2 {
-                    self.rom_banks.push([0u8; 0x4000]);
-                }
-            }
-            0x01 => {
-                for _ in 0..4 {
-                    self.rom_banks.push([0u8; 0x4000]);
-                }
-            }
-            0x02 => {
-                for _ in 0..8 {
-                    self.rom_banks.push([0u8; 0x4000]);
-                }
-            }
-            0x03 => {
-                for _ in 0..16 {
-                    self.rom_banks.push([0u8; 0x4000]);
-                }
-            }
-            0x04 => {
-                for _ in 0..32 {
-                    self.rom_banks.push([0u8; 0x4000]);
-                }
-            }
-            0x05 => {
-                for _ in 0..64 {
-                    self.rom_banks.push([0u8; 0x4000]);
-                }
+            interrupts: Interrupts::new(),
+            serial: Serial::new(),
+            timer: Timer::new(),
+            ppu: Ppu::new(),
+            apu: Apu::new(),
+            dma: Dma::new(),
+            joypad: Joypad::new(),
+            cartridge: Cartridge::empty(),
+            boot_rom: None,
+            save_dir: None,
+            save_path: None,
+            saved_data: Vec::new(),
+            frames: 0,
+            cycles: 0,
+            locked: false,
+            halted: false,
+            halt_bug: false,
+            stopped: false,
+            branch_taken: false,
+            frame_cycles: 0,
+        }
+    }
+
+    /// Power on with the cartridge in `rom`, without a save file.
+    ///
+    /// Battery backed RAM can be restored with [`load_save_ram`](Gameboy::load_save_ram)
+    /// and read back with [`save_ram`](Gameboy::save_ram).
+    pub fn from_rom_bytes(rom: Vec<u8>) -> Result<Gameboy, EmulatorError> {
+        let mut gameboy: Gameboy = Gameboy::new();
+        gameboy.cartridge = Cartridge::new(rom)?;
+        Ok(gameboy)
+    }
+
+    /// Set the buttons currently held, one bit per [`Button`] as given by [`Button::mask`].
+    pub fn set_buttons(&mut self, buttons: u8) {
+        if self.joypad.set_buttons(buttons) {
+            self.interrupts.request(Interrupt::Joypad);
+        }
+    }
+
+    /// Shade 0 (lightest) to 3 (darkest) of every LCD pixel, row by row.
+    pub fn frame_buffer(&self) -> &[u8; SCREEN_WIDTH * SCREEN_HEIGHT] {
+        self.ppu.frame_buffer()
+    }
+
+    /// Interleaved left/right samples at [`SAMPLE_RATE`] produced since the
+    /// last call. At most a second of audio is kept between calls.
+    pub fn audio_samples(&mut self) -> Vec<f32> {
+        self.apu.take_samples()
+    }
+
+    /// Leave sound channels out of [`audio_samples`](Gameboy::audio_samples),
+    /// bit 0 of `muted` is channel 1 and bit 3 is channel 4.
+    pub fn set_muted_channels(&mut self, muted: u8) {
+        self.apu.set_muted(muted);
+    }
+
+    /// Frames completed since power on.
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// M-cycles run since power on.
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Directory for .sav files instead of next to the ROM, set before [`load_rom`](Gameboy::load_rom).
+    pub fn set_save_dir(&mut self, save_dir: impl AsRef<Path>) {
+        self.save_dir = Some(save_dir.as_ref().to_path_buf());
+    }
+
+    /// Run the 256 byte DMG boot ROM at `path` from 0x0000 until the game unmaps it through 0xFF50.
+    pub fn load_boot_rom(&mut self, path: impl AsRef<Path>) -> Result<(), EmulatorError> {
+        let path: &Path = path.as_ref();
+        let bytes: Vec<u8> = fs::read(path)?;
+        if bytes.len() != 0x100 {
+            return Err(EmulatorError::Config(format!(
+                "{}: boot ROM must be 256 bytes, found {}",
+                path.display(),
+                bytes.len()
+            )));
+        }
+        self.boot_rom = Some(bytes);
+        self.registers.set_pc(0x0000);
+        Ok(())
+    }
+
+    /// Insert the cartridge in the ROM file at `path`.
+    ///
+    /// If the cartridge has a battery its RAM is loaded from and saved to a
+    /// .sav file next to the ROM, or in the directory given to
+    /// [`set_save_dir`](Gameboy::set_save_dir).
+    pub fn load_rom(&mut self, path: impl AsRef<Path>) -> Result<(), EmulatorError> {
+        let path: &Path = path.as_ref();
+        let bytes: Vec<u8> = fs::read(path)?;
+
+        self.cartridge = Cartridge::new(bytes)?;
+        self.save_path = None;
+
+        // Battery backed RAM lives next to the ROM as game.sav
+        if self.cartridge.has_battery() {
+            // Fail here rather than on the first save
+            if let Some(save_dir) = &self.save_dir {
+                fs::create_dir_all(save_dir)?;
+            }
+            let save_path: PathBuf = match (&self.save_dir, path.file_name()) {
+                (Some(save_dir), Some(file_name)) => save_dir.join(file_name).with_extension("sav"),
+                _ => path.with_extension("sav"),
+            };
+            if save_path.exists() {
+                let data: Vec<u8> = fs::read(&save_path)?;
+                self.cartridge.load_save_data(&data);
+            }
+            self.saved_data = self.cartridge.save_data();
+            self.save_path = Some(save_path);
+        }
+        Ok(())
+    }
+
+    /// Write battery backed RAM to the .sav file if it changed since the last save.
+    ///
+    /// The core never saves on its own, call it every few seconds while
+    /// running and once more before exiting.
+    pub fn save(&mut self) -> Result<(), EmulatorError> {
+        if let Some(save_path) = &self.save_path {
+            let data: Vec<u8> = self.cartridge.save_data();
+            if data != self.saved_data {
+                fs::write(save_path, &data)?;
+                self.saved_data = data;
             }
-            0x06 => {
-                for _ in 0..128 {
-                    self.rom_banks.push([0u8; 0x4000]);
+        }
+        Ok(())
+    }
+
+    // CPU write, only HRAM and IO can be reached during OAM DMA
+    fn write(&mut self, addr: u16, d8: u8) {
+        if self.dma.is_active() && addr < 0xFF00 {
+            return;
+        }
+        self.bus_write(addr, d8);
+    }
+
+    // CPU read, the bus is held by OAM DMA outside HRAM and IO
+    fn read(&self, addr: u16) -> u8 {
+        if self.dma.is_active() && addr < 0xFF00 {
+            return 0xFF;
+        }
+        self.bus_read(addr)
+    }
+
+    // Write d8 to the device mapped at addr
+    fn bus_write(&mut self, addr: u16, d8: u8) {
+        match addr {
+            0x0000..=0x7FFF => self.cartridge.write_rom(addr, d8),
+            0x8000..=0x9FFF => self.ppu.write_vram(addr, d8),
+            0xA000..=0xBFFF => self.cartridge.write_ram(addr, d8),
+            // Echo RAM, mirror of 0xC000-0xDDFF
+            0xE000..=0xFDFF => self.memory[addr as usize - 0x2000] = d8,
+            0xFE00..=0xFE9F => self.ppu.write_oam(addr, d8),
+            0xFEA0..=0xFEFF => {}
+            0xFF00 => {
+                if self.joypad.write(d8) {
+                    self.interrupts.request(Interrupt::Joypad);
                 }
             }
-            0x07 => {
-                for _ in 0..256 {
-                    self.rom_banks.push([0u8; 0x4000]);
+            0xFF01..=0xFF02 => self.serial.write(addr, d8),
+            0xFF04..=0xFF07 => self.timer.write(addr, d8),
+            0xFF0F => self.interrupts.set_if(d8),
+            0xFF10..=0xFF3F => self.apu.write(addr, d8),
+            0xFF46 => self.dma.write(d8),
+            0xFF40..=0xFF4B => self.ppu.write(addr, d8, &mut self.interrupts),
+            // Writing a non-zero value unmaps the boot ROM until reset
+            0xFF50 => {
+                if d8 != 0 {
+                    self.boot_rom = None;
                 }
             }
-            0x52 => {
-                for _ in 0..72 {
-                    self.rom_banks.push([0u8; 0x4000]);
-                }
+            0xFFFF => self.interrupts.set_ie(d8),
+            // Unmapped IO
+            0xFF03 | 0xFF08..=0xFF0E | 0xFF4C..=0xFF7F => {}
+            _ => self.memory[addr as usize] = d8,
+        }
+    }
+
+    // Read from the device mapped at addr
+    fn bus_read(&self, addr: u16) -> u8 {
+        if let Some(boot_rom) = &self.boot_rom {
+            if addr < 0x0100 {
+                return boot_rom[addr as usize];
             }
-            0x53 => {
-                for _ in 0..80 {
-                    self.rom_banks.push([0u8; 0x4000]);
-                }
+        }
+
+        match addr {
+            0x0000..=0x7FFF => self.cartridge.read_rom(addr),
+            0x8000..=0x9FFF => self.ppu.read_vram(addr),
+            0xA000..=0xBFFF => self.cartridge.read_ram(addr),
+            0xE000..=0xFDFF => self.memory[addr as usize - 0x2000],
+            0xFE00..=0xFE9F => self.ppu.read_oam(addr),
+            0xFEA0..=0xFEFF => self.ppu.read_prohibited(),
+            0xFF00 => self.joypad.read(),
+            0xFF01..=0xFF02 => self.serial.read(addr),
+            0xFF04..=0xFF07 => self.timer.read(addr),
+            0xFF0F => self.interrupts.get_if(),
+            0xFF10..=0xFF3F => self.apu.read(addr),
+            0xFF46 => self.dma.read(),
+            0xFF40..=0xFF4B => self.ppu.read(addr),
+            0xFF50 => 0xFF,
+            0xFFFF => self.interrupts.get_ie(),
+            0xFF03 | 0xFF08..=0xFF0E | 0xFF4C..=0xFF7F => 0xFF,
+            _ => self.memory[addr as usize],
+        }
+    }
+
+    // Get u8 at pc location and increment
+    fn get_at_pc_incr(&mut self) -> u8 {
+        let value: u8 = self.read(self.registers.get_pc());
+        self.registers.set_pc(self.registers.get_pc().wrapping_add(1));
+        value
+    }
+
+    fn get_next_16(&mut self) -> u16 {
+        self.get_at_pc_incr() as u16 | ((self.get_at_pc_incr() as u16) << 8)
+    }
+
+    // Read the value of a register or (HL)
+    fn read_target(&self, target: Target) -> u8 {
+        match target {
+            Target::Reg(reg) => self.registers.get_reg_8(reg),
+            Target::HL => self.read(self.registers.get_hl()),
+        }
+    }
+
+    // Write to a register or (HL)
+    fn write_target(&mut self, target: Target, d8: u8) {
+        match target {
+            Target::Reg(reg) => self.registers.set_reg_8(reg, d8),
+            Target::HL => {
+                let hl: u16 = self.registers.get_hl();
+                self.write(hl, d8);
             }
-            0x54 => {
-                for _ in 0..96 {
-                    self.rom_banks.push([0u8; 0x4000]);
+        }
+    }
+
+    // Jump to the highest priority pending interrupt, returns the M-cycles taken
+    fn handle_interrupts(&mut self) -> u32 {
+        match self.interrupts.acknowledge() {
+            Some(interrupt) => {
+                // EI; HALT with an interrupt pending returns to the HALT itself
+                let mut pc: u16 = self.registers.get_pc();
+                if self.halt_bug {
+                    self.halt_bug = false;
+                    pc = pc.wrapping_sub(1);
                 }
+                self.push_d16(pc);
+                self.registers.set_pc(interrupt.vector());
+                DISPATCH_CYCLES
             }
-            _ => panic!("Unknown ROM size {}", rom_size),
+            None => 0,
         }
+    }
 
-        match ram_size {
-            0x00 => {}
-            0x01 => self.ram_banks.push([0u8; 0x2000]),
-            0x02 => self.ram_banks.push([0u8; 0x2000]),
-            0x03 => {
-                for _ in 0..4 {
-                    self.ram_banks.push([0u8; 0x2000]);
-                }
+    // Advance every peripheral by the M-cycles the CPU just spent
+    fn tick(&mut self, cycles: u32) {
+        self.frame_cycles += cycles;
+        self.cycles += cycles as u64;
+
+        for _ in 0..cycles {
+            if let Some((source, destination)) = self.dma.tick() {
+                let d8: u8 = self.bus_read(source);
+                self.ppu.write_oam(destination, d8);
             }
-            _ => panic!("Unknown ram size {}", ram_size),
         }
+        self.dma.start_pending();
 
-        for (i, b) in bytes.into_iter().enumerate() {
-            self.rom_banks[i / 0x4000][i % 0x4000] = b;
+        self.ppu.tick(cycles, &mut self.interrupts);
+        self.apu.tick(cycles);
+        self.cartridge.tick(cycles);
+
+        if self.timer.tick(cycles) {
+            self.interrupts.request(Interrupt::Timer);
+        }
+
+        if self.serial.tick(cycles) {
+            self.interrupts.request(Interrupt::Serial);
         }
-        Ok(())
     }
 
-    // Write d8 to memory[addr]
-    pub fn write(&mut self, addr: u16, d8: u8) {
-        if addr < 0x2000 {
-            // RAM enabled
-            self.ram_enabled = (d8 & 0x0F) == 0x0A;
-        } else if addr < 0x4000 {
-            // ROM bank 5 lsb switching
-            let mut bank = d8 & 0x1F;
-            if bank == 0x00 {
-                bank = 0x01;
-            }
-            let bank = bank | (self.selected_rom_bank as u8 & (!0x1F));
-            self.selected_rom_bank = bank as usize;
-        } else if addr < 0x6000 {
-            // RAM bank 2 msb or RAM bank
-            let bank = d8 & 0x03;
-            if self.rxm_mode == 0x00 {
-                let bank = (bank << 5) | (self.selected_rom_bank as u8 & 0x1F);
-                self.selected_rom_bank = bank as usize;
-            } else {
-                self.selected_ram_bank = bank as usize;
-            }
-        } else if addr < 0x8000 {
-            // ROM/RAM mode
-            if d8 != 0x00 && d8 != 0x01 {
-                panic!("Changing ROM/RAM mode to {}", d8);
-            } else {
-                self.rxm_mode = d8;
-            }
+    /// Battery backed cartridge RAM (and clock) in the .sav layout, empty for
+    /// carts without a battery.
+    pub fn save_ram(&self) -> Vec<u8> {
+        if !self.cartridge.has_battery() {
+            return Vec::new();
         }
+        self.cartridge.save_data()
+    }
 
-        self.memory[addr as usize] = d8;
-    }
-
-    pub fn read(&self, addr: u16) -> u8 {
-        if addr < 0x4000 {
-            // Read from ROM bank 0
-            (self.rom_banks[0])[addr as usize - 0x0000]
-        } else if addr < 0x8000 {
-            // Read from ROM bank n
-            (self.rom_banks[self.selected_rom_bank])[addr as usize - 0x4000]
-        } else if addr < 0xA000 {
-            // VRAM
-            self.memory[addr as usize]
-        } else if addr >= 0xA000 && addr < 0xC000 {
-            // Read from RAM bank n
-            if self.ram_enabled {
-                (self.ram_banks[self.selected_ram_bank])[addr as usize - 0xA000]
-            } else {
-                panic!("Accessing RAM while disabled");
-            }
-        } else {
-            self.memory[addr as usize]
+    /// Restore battery backed cartridge RAM from [`save_ram`](Gameboy::save_ram) data.
+    pub fn load_save_ram(&mut self, data: &[u8]) {
+        if self.cartridge.has_battery() {
+            self.cartridge.load_save_data(data);
+            self.saved_data = self.cartridge.save_data();
         }
     }
 
-    // Get u8 at pc location and increment
-    pub fn get_at_pc_incr(&mut self) -> u8 {
-        let value: u8 = self.read(self.registers.get_pc());
-        self.registers.set_pc(&self.registers.get_pc() + 1);
-        value
+    /// Run one instruction (or one M-cycle while halted or stopped), returns
+    /// true when it completed a frame.
+    ///
+    /// An illegal opcode returns [`EmulatorError::IllegalOpcode`] once, after
+    /// which the CPU stays locked like the real hardware.
+    pub fn step_instruction(&mut self) -> Result<bool, EmulatorError> {
+        self.execute()?;
+
+        if self.frame_cycles < CYCLES_PER_FRAME {
+            return Ok(false);
+        }
+        self.frame_cycles -= CYCLES_PER_FRAME;
+
+        self.frames += 1;
+        Ok(true)
     }
 
-    pub fn get_next_16(&mut self) -> u16 {
-        self.get_at_pc_incr() as u16 | ((self.get_at_pc_incr() as u16) << 8)
+    /// Run until the next frame is ready in [`frame_buffer`](Gameboy::frame_buffer).
+    pub fn run_frame(&mut self) -> Result<(), EmulatorError> {
+        while !self.step_instruction()? {}
+        Ok(())
     }
 
-    pub fn step(&mut self) -> Result<bool, String> {
-        static mut STEP_COUNT: u64 = 0;
-        static START_PRINT: u64 = 1500000;
+    fn execute(&mut self) -> Result<(), EmulatorError> {
+        // An illegal opcode hangs the CPU until the system is reset
+        if self.locked {
+            self.tick(1);
+            return Ok(());
+        }
 
-        if unsafe { STEP_COUNT } % 0x1FF == 0 {
-            if !self.window.event_loop() {
-                return Ok(false);
+        // STOP sleeps until a selected P1 line goes low, nothing but the
+        // frame pacing and the cycle count keep running
+        if self.stopped {
+            if self.joypad.lines() == 0x0F {
+                self.frame_cycles += 1;
+                self.cycles += 1;
+                return Ok(());
             }
-            self.window.display_loop(&self.memory)?;
+            self.stopped = false;
+        }
 
-            let mut ly: u8 = self.read(0xFF44);
-            if ly == 153 {
-                ly = 0;
-            } else {
-                ly += 1;
+        // HALT sleeps until any enabled interrupt is requested, even with IME off
+        if self.halted {
+            if self.interrupts.pending() == 0 {
+                self.tick(1);
+                return Ok(());
             }
-            self.write(0xFF44, ly);
+            self.halted = false;
         }
 
-        if cfg!(debug_assertions) && unsafe { STEP_COUNT > START_PRINT } {
-            println!("Before {:?}", self.registers);
+        // Servicing an interrupt takes the place of an instruction
+        let interrupt_cycles: u32 = self.handle_interrupts();
+        if interrupt_cycles > 0 {
+            self.tick(interrupt_cycles);
+            return Ok(());
         }
 
         let instruction = self.get_at_pc_incr();
 
-        if cfg!(debug_assertions) && unsafe { STEP_COUNT > START_PRINT } {
-            println!("Step: {}\nInstruction: 0x{:02X}", unsafe{ STEP_COUNT }, instruction);
+        // HALT bug, the byte after HALT is read twice
+        if self.halt_bug {
+            self.halt_bug = false;
+            self.registers.set_pc(self.registers.get_pc().wrapping_sub(1));
         }
 
-        unsafe { STEP_COUNT += 1 };
+        let mut cycles: u32 = if instruction == 0xcb {
+            prefixed_cycles(self.read(self.registers.get_pc()))
+        } else {
+            OPCODE_CYCLES[instruction as usize]
+        };
+        self.branch_taken = false;
 
         match instruction {
             // 0x
@@ -235,17 +489,12 @@ impl Gameboy {
             0x04 => self.inc_8(Reg8::B),
             0x05 => self.dec_8(Reg8::B),
             0x06 => self.ld_d8(Reg8::B),
-            0x07 => {
-                let a: u8 = self.registers.get_a();
-
-                self.registers.set_flag(Flag::C, a & 0x80 == 0x80);
-                self.registers.set_a(a << 1);
-            }
+            0x07 => self.rlca(),
             0x08 => {
                 let sp: u16 = self.registers.get_sp();
                 let a16: u16 = self.get_next_16();
-                self.write(a16, (sp >> 8) as u8);
-                self.write(a16 + 1, sp as u8)
+                self.write(a16, sp as u8);
+                self.write(a16.wrapping_add(1), (sp >> 8) as u8)
             }
 
             0x09 => self.add_hl(Reg16::BC),
@@ -258,6 +507,7 @@ impl Gameboy {
             0x0c => self.inc_8(Reg8::C),
             0x0d => self.dec_8(Reg8::C),
             0x0e => self.ld_d8(Reg8::C),
+            0x0f => self.rrca(),
 
             // 1x
             0x10 => self.stop(),
@@ -271,7 +521,7 @@ impl Gameboy {
             0x14 => self.inc_8(Reg8::D),
             0x15 => self.dec_8(Reg8::D),
             0x16 => self.ld_d8(Reg8::D),
-
+            0x17 => self.rla(),
             0x18 => self.jr(true),
             0x19 => self.add_hl(Reg16::DE),
             0x1a => {
@@ -283,18 +533,7 @@ impl Gameboy {
             0x1c => self.inc_8(Reg8::E),
             0x1d => self.dec_8(Reg8::E),
             0x1e => self.ld_d8(Reg8::E),
-
-            0x1f => {
-                let mut a: u8 = self.registers.get_a();
-                let carry: bool = self.registers.get_flag(Flag::C);
-
-                self.registers.set_flag(Flag::C, a & 0x01 == 0x01);
-                a >>= 1;
-                if carry {
-                    a += 0x80;
-                }
-                self.registers.set_a(a);
-            }
+            0x1f => self.rra(),
 
             // 2x
             0x20 => self.jr(self.registers.get_flag(Flag::Z) == false),
@@ -303,25 +542,24 @@ impl Gameboy {
                 let a: u8 = self.registers.get_a();
                 let hl: u16 = self.registers.get_hl();
                 self.write(hl, a);
-                self.registers.set_hl(hl + 1);
+                self.registers.set_hl(hl.wrapping_add(1));
             }
             0x23 => self.inc_16(Reg16::HL),
             0x24 => self.inc_8(Reg8::H),
             0x25 => self.dec_8(Reg8::H),
             0x26 => self.ld_d8(Reg8::H),
-
+            0x27 => self.daa(),
             0x28 => self.jr(self.registers.get_flag(Flag::Z) == true),
             0x29 => self.add_hl(Reg16::HL),
-
-            0x2b => self.dec_16(Reg16::HL),
             0x2a => {
                 let hl: u16 = self.registers.get_hl();
                 let d8: u8 = self.read(hl);
                 self.registers.set_a(d8);
-                self.registers.set_hl(hl + 1);
+                self.registers.set_hl(hl.wrapping_add(1));
             }
+            0x2b => self.dec_16(Reg16::HL),
             0x2c => self.inc_8(Reg8::L),
-            0x2d => self.inc_8(Reg8::L),
+            0x2d => self.dec_8(Reg8::L),
             0x2e => self.ld_d8(Reg8::L),
             0x2f => self.cpl(),
 
@@ -332,29 +570,40 @@ impl Gameboy {
                 let a: u8 = self.registers.get_a();
                 let hl: u16 = self.registers.get_hl();
                 self.write(hl, a);
-                self.registers.set_hl(hl - 1);
+                self.registers.set_hl(hl.wrapping_sub(1));
             }
             0x33 => self.inc_16(Reg16::SP),
-
+            0x34 => {
+                let hl: u16 = self.registers.get_hl();
+                let d8: u8 = self.read(hl);
+                let value: u8 = self.inc(d8);
+                self.write(hl, value);
+            }
+            0x35 => {
+                let hl: u16 = self.registers.get_hl();
+                let d8: u8 = self.read(hl);
+                let value: u8 = self.dec(d8);
+                self.write(hl, value);
+            }
             0x36 => {
                 let d8: u8 = self.get_at_pc_incr();
                 let hl: u16 = self.registers.get_hl();
                 self.write(hl, d8)
             }
-
+            0x37 => self.scf(),
             0x38 => self.jr(self.registers.get_flag(Flag::C) == true),
             0x39 => self.add_hl(Reg16::SP),
             0x3a => {
                 let hl: u16 = self.registers.get_hl();
                 let d8: u8 = self.read(hl);
                 self.registers.set_a(d8);
-                self.registers.set_hl(hl - 1);
+                self.registers.set_hl(hl.wrapping_sub(1));
             }
-
             0x3b => self.dec_16(Reg16::SP),
             0x3c => self.inc_8(Reg8::A),
             0x3d => self.dec_8(Reg8::A),
             0x3e => self.ld_d8(Reg8::A),
+            0x3f => self.ccf(),
 
             // 4x
             0x40 => self.ld_reg8(Reg8::B, Reg8::B),
@@ -465,7 +714,7 @@ impl Gameboy {
                 let hl: u16 = self.registers.get_hl();
                 self.write(hl, l);
             }
-            0x76 => return Ok(false),
+            0x76 => self.halt(),
             0x77 => {
                 let a: u8 = self.registers.get_a();
                 let hl: u16 = self.registers.get_hl();
@@ -497,6 +746,44 @@ impl Gameboy {
                 self.add_a(d8);
             }
             0x87 => self.add_a(self.registers.get_a()),
+            0x88 => self.adc(self.registers.get_b()),
+            0x89 => self.adc(self.registers.get_c()),
+            0x8a => self.adc(self.registers.get_d()),
+            0x8b => self.adc(self.registers.get_e()),
+            0x8c => self.adc(self.registers.get_h()),
+            0x8d => self.adc(self.registers.get_l()),
+            0x8e => {
+                let hl: u16 = self.registers.get_hl();
+                let d8: u8 = self.read(hl);
+                self.adc(d8);
+            }
+            0x8f => self.adc(self.registers.get_a()),
+
+            // 9x
+            0x90 => self.sub(self.registers.get_b()),
+            0x91 => self.sub(self.registers.get_c()),
+            0x92 => self.sub(self.registers.get_d()),
+            0x93 => self.sub(self.registers.get_e()),
+            0x94 => self.sub(self.registers.get_h()),
+            0x95 => self.sub(self.registers.get_l()),
+            0x96 => {
+                let hl: u16 = self.registers.get_hl();
+                let d8: u8 = self.read(hl);
+                self.sub(d8);
+            }
+            0x97 => self.sub(self.registers.get_a()),
+            0x98 => self.sbc(self.registers.get_b()),
+            0x99 => self.sbc(self.registers.get_c()),
+            0x9a => self.sbc(self.registers.get_d()),
+            0x9b => self.sbc(self.registers.get_e()),
+            0x9c => self.sbc(self.registers.get_h()),
+            0x9d => self.sbc(self.registers.get_l()),
+            0x9e => {
+                let hl: u16 = self.registers.get_hl();
+                let d8: u8 = self.read(hl);
+                self.sbc(d8);
+            }
+            0x9f => self.sbc(self.registers.get_a()),
 
             // ax
             0xa0 => {
@@ -639,9 +926,12 @@ impl Gameboy {
             0xc1 => self.pop_d16_into(Reg16::BC),
             0xc2 => self.jmp(self.registers.get_flag(Flag::Z) == false),
             0xc3 => self.jmp(true),
-
+            0xc4 => self.call(self.registers.get_flag(Flag::Z) == false),
             0xc5 => self.push_d16(self.registers.get_bc()),
-
+            0xc6 => {
+                let d8: u8 = self.get_at_pc_incr();
+                self.add_a(d8);
+            }
             0xc7 => self.rst(0x00),
             0xc8 => self.ret(self.registers.get_flag(Flag::Z) == true),
             0xc9 => self.ret(true),
@@ -649,164 +939,57 @@ impl Gameboy {
             0xcb => {
                 let prefixed_instruction: u8 = self.get_at_pc_incr();
 
-                if cfg!(debug_assertions) && unsafe { STEP_COUNT > START_PRINT } {
-                    println!("Prefixed Instruction: 0x{:02X}", prefixed_instruction);
-                }
-
-                match prefixed_instruction {
-                    // 3x
-                    0x30 => self.swap(Reg8::B),
-                    0x31 => self.swap(Reg8::C),
-                    0x32 => self.swap(Reg8::D),
-                    0x33 => self.swap(Reg8::E),
-                    0x34 => self.swap(Reg8::H),
-                    0x35 => self.swap(Reg8::L),
-                    0x36 => {
-                        let hl: u16 = self.registers.get_hl();
-                        let mut r: u8 = self.read(hl);
-                        let r_bottom: u8 = r & 0x0F;
-                        r >>= 4;
-                        r |= r_bottom << 4;
-                        self.write(hl, r);
-                    }
-                    0x37 => self.swap(Reg8::A),
-
-                    // 8x
-                    0x80 => self.res(0, Reg8::B),
-                    0x81 => self.res(0, Reg8::C),
-                    0x82 => self.res(0, Reg8::D),
-                    0x83 => self.res(0, Reg8::E),
-                    0x84 => self.res(0, Reg8::H),
-                    0x85 => self.res(0, Reg8::L),
-                    0x86 => {
-                        let hl: u16 = self.registers.get_hl();
-                        let d8: u8 = self.read(hl);
-                        let mask: u8 = !(0x01 << 0);
-                        self.write(hl, d8 & mask);
-                    }
-                    0x87 => self.res(1, Reg8::A),
-                    0x88 => self.res(1, Reg8::B),
-                    0x89 => self.res(1, Reg8::C),
-                    0x8a => self.res(1, Reg8::D),
-                    0x8b => self.res(1, Reg8::E),
-                    0x8c => self.res(1, Reg8::H),
-                    0x8d => self.res(1, Reg8::L),
-                    0x8e => {
-                        let hl: u16 = self.registers.get_hl();
-                        let d8: u8 = self.read(hl);
-                        let mask: u8 = !(0x01 << 1);
-                        self.write(hl, d8 & mask);
-                    }
-                    0x8f => self.res(1, Reg8::A),
-
-                    // 9x
-                    0x90 => self.res(2, Reg8::B),
-                    0x91 => self.res(2, Reg8::C),
-                    0x92 => self.res(2, Reg8::D),
-                    0x93 => self.res(2, Reg8::E),
-                    0x94 => self.res(2, Reg8::H),
-                    0x95 => self.res(2, Reg8::L),
-                    0x96 => {
-                        let hl: u16 = self.registers.get_hl();
-                        let d8: u8 = self.read(hl);
-                        let mask: u8 = !(0x01 << 2);
-                        self.write(hl, d8 & mask);
-                    }
-                    0x97 => self.res(2, Reg8::A),
-                    0x98 => self.res(3, Reg8::B),
-                    0x99 => self.res(3, Reg8::C),
-                    0x9a => self.res(3, Reg8::D),
-                    0x9b => self.res(3, Reg8::E),
-                    0x9c => self.res(3, Reg8::H),
-                    0x9d => self.res(3, Reg8::L),
-                    0x9e => {
-                        let hl: u16 = self.registers.get_hl();
-                        let d8: u8 = self.read(hl);
-                        let mask: u8 = !(0x01 << 3);
-                        self.write(hl, d8 & mask);
-                    }
-                    0x9f => self.res(3, Reg8::A),
-
-                    // ax
-                    0xa0 => self.res(4, Reg8::B),
-                    0xa1 => self.res(4, Reg8::C),
-                    0xa2 => self.res(4, Reg8::D),
-                    0xa3 => self.res(4, Reg8::E),
-                    0xa4 => self.res(4, Reg8::H),
-                    0xa5 => self.res(4, Reg8::L),
-                    0xa6 => {
-                        let hl: u16 = self.registers.get_hl();
-                        let d8: u8 = self.read(hl);
-                        let mask: u8 = !(0x01 << 4);
-                        self.write(hl, d8 & mask);
-                    }
-                    0xa7 => self.res(4, Reg8::A),
-                    0xa8 => self.res(5, Reg8::B),
-                    0xa9 => self.res(5, Reg8::C),
-                    0xaa => self.res(5, Reg8::D),
-                    0xab => self.res(5, Reg8::E),
-                    0xac => self.res(5, Reg8::H),
-                    0xad => self.res(5, Reg8::L),
-                    0xae => {
-                        let hl: u16 = self.registers.get_hl();
-                        let d8: u8 = self.read(hl);
-                        let mask: u8 = !(0x01 << 5);
-                        self.write(hl, d8 & mask);
-                    }
-                    0xaf => self.res(5, Reg8::A),
-
-                    // bx
-                    0xb0 => self.res(6, Reg8::B),
-                    0xb1 => self.res(6, Reg8::C),
-                    0xb2 => self.res(6, Reg8::D),
-                    0xb3 => self.res(6, Reg8::E),
-                    0xb4 => self.res(6, Reg8::H),
-                    0xb5 => self.res(6, Reg8::L),
-                    0xb6 => {
-                        let hl: u16 = self.registers.get_hl();
-                        let d8: u8 = self.read(hl);
-                        let mask: u8 = !(0x01 << 6);
-                        self.write(hl, d8 & mask);
-                    }
-                    0xb7 => self.res(6, Reg8::A),
-                    0xb8 => self.res(7, Reg8::B),
-                    0xb9 => self.res(7, Reg8::C),
-                    0xba => self.res(7, Reg8::D),
-                    0xbb => self.res(7, Reg8::E),
-                    0xbc => self.res(7, Reg8::H),
-                    0xbd => self.res(7, Reg8::L),
-                    0xbe => {
-                        let hl: u16 = self.registers.get_hl();
-                        let d8: u8 = self.read(hl);
-                        let mask: u8 = !(0x01 << 7);
-                        self.write(hl, d8 & mask);
-                    }
-                    0xbf => self.res(7, Reg8::A),
-
-                    _ => {
-                        println!("0xCB{:02X} Not implemented", prefixed_instruction);
-                        return Ok(false);
+                // Bits 0-2 select the operand, bits 3-5 the bit or operation
+                let target: Target = Target::from_bits(prefixed_instruction);
+                let b: u8 = (prefixed_instruction >> 3) & 0x07;
+
+                match prefixed_instruction >> 6 {
+                    0b00 => match b {
+                        0 => self.rlc(target),
+                        1 => self.rrc(target),
+                        2 => self.rl(target),
+                        3 => self.rr(target),
+                        4 => self.sla(target),
+                        5 => self.sra(target),
+                        6 => self.swap(target),
+                        _ => self.srl(target),
                     },
+                    0b01 => self.bit(b, target),
+                    0b10 => self.res(b, target),
+                    _ => self.set(b, target),
                 }
             }
 
+            0xcc => self.call(self.registers.get_flag(Flag::Z) == true),
             0xcd => self.call(true),
-
+            0xce => {
+                let d8: u8 = self.get_at_pc_incr();
+                self.adc(d8);
+            }
             0xcf => self.rst(0x08),
 
             // dx
             0xd0 => self.ret(self.registers.get_flag(Flag::C) == false),
             0xd1 => self.pop_d16_into(Reg16::DE),
-
             0xd2 => self.jmp(self.registers.get_flag(Flag::C) == false),
-
+            0xd4 => self.call(self.registers.get_flag(Flag::C) == false),
             0xd5 => self.push_d16(self.registers.get_de()),
-
+            0xd6 => {
+                let d8: u8 = self.get_at_pc_incr();
+                self.sub(d8);
+            }
             0xd7 => self.rst(0x10),
             0xd8 => self.ret(self.registers.get_flag(Flag::C) == true),
-
+            0xd9 => {
+                self.ret(true);
+                self.interrupts.set_ime(true);
+            }
             0xda => self.jmp(self.registers.get_flag(Flag::C) == true),
-
+            0xdc => self.call(self.registers.get_flag(Flag::C) == true),
+            0xde => {
+                let d8: u8 = self.get_at_pc_incr();
+                self.sbc(d8);
+            }
             0xdf => self.rst(0x18),
 
             // ex
@@ -817,7 +1000,6 @@ impl Gameboy {
                 self.write(0xFF00 + offset as u16, a);
             }
             0xe1 => self.pop_d16_into(Reg16::HL),
-
             0xe2 => {
                 let c: u8 = self.registers.get_c();
                 let a: u8 = self.registers.get_a();
@@ -830,7 +1012,10 @@ impl Gameboy {
                 self.and(d8);
             }
             0xe7 => self.rst(0x20),
-
+            0xe8 => {
+                let sp: u16 = self.add_sp_r8();
+                self.registers.set_sp(sp);
+            }
             0xe9 => {
                 let hl: u16 = self.registers.get_hl();
                 self.registers.set_pc(hl);
@@ -843,7 +1028,10 @@ impl Gameboy {
                 let a: u8 = self.registers.get_a();
                 self.write(addr, a);
             }
-
+            0xee => {
+                let d8: u8 = self.get_at_pc_incr();
+                self.xor(d8);
+            }
             0xef => self.rst(0x28),
 
             // fx
@@ -852,19 +1040,38 @@ impl Gameboy {
                 let d8: u8 = self.read(0xFF00 + a8 as u16);
                 self.registers.set_a(d8);
             }
-            0xf1 => self.pop_d16_into(Reg16::AF),
-            0xf3 => self.interrupts_enabled(false),
-
+            0xf1 => {
+                // The lower nibble of F is always 0
+                let d16: u16 = self.pop_d16();
+                self.registers.set_af(d16 & 0xFFF0);
+            }
+            0xf2 => {
+                let c: u8 = self.registers.get_c();
+                let d8: u8 = self.read(0xFF00 + c as u16);
+                self.registers.set_a(d8);
+            }
+            0xf3 => self.interrupts.set_ime(false),
             0xf5 => self.push_d16(self.registers.get_af()),
-
+            0xf6 => {
+                let d8: u8 = self.get_at_pc_incr();
+                self.or(d8);
+            }
             0xf7 => self.rst(0x30),
+            0xf8 => {
+                let hl: u16 = self.add_sp_r8();
+                self.registers.set_hl(hl);
+            }
+            0xf9 => {
+                let hl: u16 = self.registers.get_hl();
+                self.registers.set_sp(hl);
+            }
 
             0xfa => {
                 let a16: u16 = self.get_next_16();
                 let d8: u8 = self.read(a16);
                 self.registers.set_a(d8);
             }
-            0xfb => self.interrupts_enabled(true),
+            0xfb => self.interrupts.enable_delayed(),
 
             0xfe => {
                 let d8: u8 = self.get_at_pc_incr();
@@ -872,24 +1079,45 @@ impl Gameboy {
             }
             0xff => self.rst(0x38),
 
-            _ => {
-                println!("0x{:02X} Not implemented", instruction);
-                return Ok(false);
-            },
+            // Illegal opcodes
+            0xd3 | 0xdb | 0xdd | 0xe3 | 0xe4 | 0xeb | 0xec | 0xed | 0xf4 | 0xfc | 0xfd => {
+                self.locked = true;
+            }
         }
 
-        if cfg!(debug_assertions) && unsafe { STEP_COUNT > START_PRINT } {
-            println!("After {:?}", self.registers);
-            println!("");
+        if self.branch_taken {
+            cycles += branch_cycles(instruction);
         }
 
-        Ok(true)
+        self.interrupts.update_ime();
+        self.tick(cycles);
+
+        if self.locked {
+            return Err(EmulatorError::IllegalOpcode {
+                opcode: instruction,
+                addr: self.registers.get_pc().wrapping_sub(1),
+            });
+        }
+        Ok(())
     }
 
     // ---Generalized instruction implementations---
 
+    // Add with carry A += n + C
+    fn adc(&mut self, n: u8) {
+        let a: u8 = self.registers.get_a();
+        let carry: u8 = self.registers.get_flag(Flag::C) as u8;
+        let value: u16 = a as u16 + n as u16 + carry as u16;
+        self.registers.set_a(value as u8);
+
+        self.registers.set_flag(Flag::Z, value as u8 == 0);
+        self.registers.set_flag(Flag::N, false);
+        self.registers.set_flag(Flag::H, (a & 0x0F) + (n & 0x0F) + carry > 0x0F);
+        self.registers.set_flag(Flag::C, value > 0xFF);
+    }
+
     // Add A += n
-    pub fn add_a(&mut self, n: u8) {
+    fn add_a(&mut self, n: u8) {
         let a: u8 = self.registers.get_a();
         let value = a.overflowing_add(n);
         self.registers.set_a(value.0);
@@ -901,7 +1129,7 @@ impl Gameboy {
     }
 
     // Add HL += n
-    pub fn add_hl(&mut self, reg: Reg16) {
+    fn add_hl(&mut self, reg: Reg16) {
         let n: u16 = self.registers.get_reg_16(reg);
         let hl: u16 = self.registers.get_hl();
 
@@ -913,8 +1141,21 @@ impl Gameboy {
         self.registers.set_flag(Flag::C, addition.1);
     }
 
+    // SP + r8, flags are computed from the low byte as an unsigned add
+    fn add_sp_r8(&mut self) -> u16 {
+        let r8: u8 = self.get_at_pc_incr();
+        let sp: u16 = self.registers.get_sp();
+
+        self.registers.set_flag(Flag::Z, false);
+        self.registers.set_flag(Flag::N, false);
+        self.registers.set_flag(Flag::H, (sp & 0x000F) + (r8 as u16 & 0x000F) > 0x000F);
+        self.registers.set_flag(Flag::C, (sp & 0x00FF) + r8 as u16 > 0x00FF);
+
+        sp.wrapping_add(r8 as i8 as u16)
+    }
+
     // And d8 with A => A 
-    pub fn and(&mut self, n: u8) {
+    fn and(&mut self, n: u8) {
         let value: u8 = self.registers.get_a() & n;
         self.registers.set_a(value);
 
@@ -924,9 +1165,19 @@ impl Gameboy {
         self.registers.set_flag(Flag::C, false);
     }
 
+    // Test bit b of target
+    fn bit(&mut self, b: u8, target: Target) {
+        let r: u8 = self.read_target(target);
+
+        self.registers.set_flag(Flag::Z, (r >> b) & 0x01 == 0x00);
+        self.registers.set_flag(Flag::N, false);
+        self.registers.set_flag(Flag::H, true);
+    }
+
     // Call a16
-    pub fn call(&mut self, condition: bool) {
+    fn call(&mut self, condition: bool) {
         let a16: u16 = self.get_next_16();
+        self.branch_taken = condition;
         if condition {
             let pc: u16 = self.registers.get_pc();
             self.push_d16(pc);
@@ -934,8 +1185,17 @@ impl Gameboy {
         }
     }
 
+    // Complement carry flag
+    fn ccf(&mut self) {
+        let carry: bool = self.registers.get_flag(Flag::C);
+
+        self.registers.set_flag(Flag::N, false);
+        self.registers.set_flag(Flag::H, false);
+        self.registers.set_flag(Flag::C, !carry);
+    }
+
     // Compare
-    pub fn cp(&mut self, n: u8) {
+    fn cp(&mut self, n: u8) {
         let a: u8 = self.registers.get_a();
 
         self.registers.set_flag(Flag::Z, a == n);
@@ -945,7 +1205,7 @@ impl Gameboy {
     }
 
     // Complement A
-    pub fn cpl(&mut self) {
+    fn cpl(&mut self) {
         let a: u8 = self.registers.get_a();
         self.registers.set_a(!a);
 
@@ -953,110 +1213,131 @@ impl Gameboy {
         self.registers.set_flag(Flag::H, true);
     }
 
-    // Decrement an 16bit register
-    pub fn dec_16(&mut self, reg: Reg16) {
-        let mut value: u16 = self.registers.get_reg_16(reg);
-        // Underflow
-        if value == 0x00 {
-            value = 0xFF;
+    // Decimal adjust A after a BCD add or subtract
+    fn daa(&mut self) {
+        let mut a: u8 = self.registers.get_a();
+        let mut carry: bool = self.registers.get_flag(Flag::C);
+
+        if self.registers.get_flag(Flag::N) {
+            if carry {
+                a = a.wrapping_sub(0x60);
+            }
+            if self.registers.get_flag(Flag::H) {
+                a = a.wrapping_sub(0x06);
+            }
         } else {
-            value -= 1;
+            if carry || a > 0x99 {
+                a = a.wrapping_add(0x60);
+                carry = true;
+            }
+            if self.registers.get_flag(Flag::H) || (a & 0x0F) > 0x09 {
+                a = a.wrapping_add(0x06);
+            }
         }
-        self.registers.set_reg_16(reg, value);
-    }
-
-    // Decrement an 8bit register
-    pub fn dec_8(&mut self, reg: Reg8) {
-        let mut value: u8 = self.registers.get_reg_8(reg);
+        self.registers.set_a(a);
 
-        self.registers.set_flag(Flag::H, (value & 0x0F).overflowing_sub(1).1);
+        self.registers.set_flag(Flag::Z, a == 0);
+        self.registers.set_flag(Flag::H, false);
+        self.registers.set_flag(Flag::C, carry);
+    }
 
-        // Underflow
-        if value == 0x00 {
-            value = 0xFF;
-        } else {
-            value -= 1;
-        }
-        self.registers.set_reg_8(reg, value);
+    // Decrement n and set flags
+    fn dec(&mut self, n: u8) -> u8 {
+        let value: u8 = n.wrapping_sub(1);
 
         self.registers.set_flag(Flag::Z, value == 0);
         self.registers.set_flag(Flag::N, true);
+        self.registers.set_flag(Flag::H, n & 0x0F == 0x00);
+        value
     }
 
-    // Increment an 16bit register
-    pub fn inc_16(&mut self, reg: Reg16) {
-        let mut value: u16 = self.registers.get_reg_16(reg);
-        // Underflow
-        if value == 0xFF {
-            value = 0x00;
-        } else {
-            value += 1;
-        }
-        self.registers.set_reg_16(reg, value);
+    // Decrement an 16bit register
+    fn dec_16(&mut self, reg: Reg16) {
+        let value: u16 = self.registers.get_reg_16(reg);
+        self.registers.set_reg_16(reg, value.wrapping_sub(1));
     }
 
-    // Increment an 8bit register
-    pub fn inc_8(&mut self, reg: Reg8) {
-        let mut value: u8 = self.registers.get_reg_8(reg);
-
-        self.registers.set_flag(Flag::H, ((value & 0x0F) + 1) & 0x10 == 0x10);
+    // Decrement an 8bit register
+    fn dec_8(&mut self, reg: Reg8) {
+        let r: u8 = self.registers.get_reg_8(reg);
+        let value: u8 = self.dec(r);
+        self.registers.set_reg_8(reg, value);
+    }
 
-        // Overflow
-        if value == 0xFF {
-            value = 0x00;
+    // Suspend the CPU until an interrupt is pending
+    fn halt(&mut self) {
+        if !self.interrupts.get_ime() && self.interrupts.pending() != 0 {
+            // With IME off and an interrupt already pending HALT exits immediately
+            // and fails to increment PC
+            self.halt_bug = true;
         } else {
-            value += 1;
+            self.halted = true;
         }
-        self.registers.set_reg_8(reg, value);
+    }
+
+    // Increment n and set flags
+    fn inc(&mut self, n: u8) -> u8 {
+        let value: u8 = n.wrapping_add(1);
 
         self.registers.set_flag(Flag::Z, value == 0);
         self.registers.set_flag(Flag::N, false);
+        self.registers.set_flag(Flag::H, n & 0x0F == 0x0F);
+        value
+    }
+
+    // Increment an 16bit register
+    fn inc_16(&mut self, reg: Reg16) {
+        let value: u16 = self.registers.get_reg_16(reg);
+        self.registers.set_reg_16(reg, value.wrapping_add(1));
     }
 
-    // Enable and disable interrupts
-    pub fn interrupts_enabled(&mut self, enabled: bool) {
-        self.write(0xFFFF, enabled as u8);
+    // Increment an 8bit register
+    fn inc_8(&mut self, reg: Reg8) {
+        let r: u8 = self.registers.get_reg_8(reg);
+        let value: u8 = self.inc(r);
+        self.registers.set_reg_8(reg, value);
     }
 
     // Jump (Un)Conditional
-    pub fn jmp(&mut self, condition: bool) {
+    fn jmp(&mut self, condition: bool) {
         let addr: u16 = self.get_next_16();
 
-        if condition{
+        self.branch_taken = condition;
+        if condition {
             self.registers.set_pc(addr);
         }
     }
 
     // Jump Relative (Un)Conditional
-    pub fn jr(&mut self, condition: bool) {
+    fn jr(&mut self, condition: bool) {
         let offset: u8 = self.get_at_pc_incr();
 
+        self.branch_taken = condition;
         if condition {
-            self.registers.set_pc((self.registers.get_pc() as i16 + (offset as i8) as i16) as u16);
-
+            self.registers.set_pc(self.registers.get_pc().wrapping_add(offset as i8 as u16));
         }
     }
 
     // LD reg <- d16
-    pub fn ld_d16(&mut self, reg: Reg16) {
+    fn ld_d16(&mut self, reg: Reg16) {
         let d16: u16 = self.get_next_16();
         self.registers.set_reg_16(reg, d16);
     }
 
     // LD reg <- d8
-    pub fn ld_d8(&mut self, reg: Reg8) {
+    fn ld_d8(&mut self, reg: Reg8) {
         let d8: u8 = self.get_at_pc_incr();
         self.registers.set_reg_8(reg, d8);
     }
 
     // LD dst <- src
-    pub fn ld_reg8(&mut self, dst: Reg8, src: Reg8) {
+    fn ld_reg8(&mut self, dst: Reg8, src: Reg8) {
         let d8: u8 = self.registers.get_reg_8(src);
         self.registers.set_reg_8(dst, d8);
     }
 
     // OR n with A => A
-    pub fn or(&mut self, n: u8) {
+    fn or(&mut self, n: u8) {
         let value: u8 = self.registers.get_a() | n;
         self.registers.set_a(value);
 
@@ -1067,65 +1348,198 @@ impl Gameboy {
     }
 
     // Push d16 to the stack
-    pub fn push_d16(&mut self, d16: u16) {
+    fn push_d16(&mut self, d16: u16) {
         let sp: u16 = self.registers.get_sp();
-        self.write(sp - 1, (d16 >> 8) as u8);
-        self.write(sp - 2, d16 as u8);
-        self.registers.set_sp(sp - 2);
+        self.write(sp.wrapping_sub(1), (d16 >> 8) as u8);
+        self.write(sp.wrapping_sub(2), d16 as u8);
+        self.registers.set_sp(sp.wrapping_sub(2));
     }
 
-    pub fn pop_d16(&mut self) -> u16 {
+    fn pop_d16(&mut self) -> u16 {
         let sp: u16 = self.registers.get_sp();
-        let d16: u16 = self.read(sp) as u16 | ((self.read(sp + 1) as u16) << 8);
-        self.registers.set_sp(sp + 2);
+        let d16: u16 = self.read(sp) as u16 | ((self.read(sp.wrapping_add(1)) as u16) << 8);
+        self.registers.set_sp(sp.wrapping_add(2));
         d16
     }
 
     // Pop d16 off the stack into reg
-    pub fn pop_d16_into(&mut self, reg: Reg16) {
+    fn pop_d16_into(&mut self, reg: Reg16) {
         let d16: u16 = self.pop_d16();
         self.registers.set_reg_16(reg, d16);
     }
 
-    // Reset bit b in reg
-    pub fn res(&mut self, b: u8, reg: Reg8) {
+    // Reset bit b in target
+    fn res(&mut self, b: u8, target: Target) {
         let mask: u8 = !(0x01 << b);
-        let r = self.registers.get_reg_8(reg);
-        self.registers.set_reg_8(reg, r & mask);
+        let r: u8 = self.read_target(target);
+        self.write_target(target, r & mask);
     }
 
     // Return
-    pub fn ret(&mut self, condition: bool) {
+    fn ret(&mut self, condition: bool) {
+        self.branch_taken = condition;
         if condition {
             let a16: u16 = self.pop_d16();
             self.registers.set_pc(a16);
         }
     }
 
+    // Rotate target left through carry
+    fn rl(&mut self, target: Target) {
+        let r: u8 = self.read_target(target);
+        let carry: u8 = self.registers.get_flag(Flag::C) as u8;
+        let value: u8 = (r << 1) | carry;
+        self.write_target(target, value);
+        self.set_shift_flags(value, r & 0x80 == 0x80);
+    }
+
+    // Rotate A left through carry
+    fn rla(&mut self) {
+        self.rl(Target::Reg(Reg8::A));
+        self.registers.set_flag(Flag::Z, false);
+    }
+
+    // Rotate target left, bit 7 into carry
+    fn rlc(&mut self, target: Target) {
+        let r: u8 = self.read_target(target);
+        let value: u8 = r.rotate_left(1);
+        self.write_target(target, value);
+        self.set_shift_flags(value, r & 0x80 == 0x80);
+    }
+
+    // Rotate A left, bit 7 into carry
+    fn rlca(&mut self) {
+        self.rlc(Target::Reg(Reg8::A));
+        self.registers.set_flag(Flag::Z, false);
+    }
+
+    // Rotate target right through carry
+    fn rr(&mut self, target: Target) {
+        let r: u8 = self.read_target(target);
+        let carry: u8 = self.registers.get_flag(Flag::C) as u8;
+        let value: u8 = (r >> 1) | (carry << 7);
+        self.write_target(target, value);
+        self.set_shift_flags(value, r & 0x01 == 0x01);
+    }
+
+    // Rotate A right through carry
+    fn rra(&mut self) {
+        self.rr(Target::Reg(Reg8::A));
+        self.registers.set_flag(Flag::Z, false);
+    }
+
+    // Rotate target right, bit 0 into carry
+    fn rrc(&mut self, target: Target) {
+        let r: u8 = self.read_target(target);
+        let value: u8 = r.rotate_right(1);
+        self.write_target(target, value);
+        self.set_shift_flags(value, r & 0x01 == 0x01);
+    }
+
+    // Rotate A right, bit 0 into carry
+    fn rrca(&mut self) {
+        self.rrc(Target::Reg(Reg8::A));
+        self.registers.set_flag(Flag::Z, false);
+    }
+
     // Call at offset address
-    pub fn rst(&mut self, offset: u8) {
+    fn rst(&mut self, offset: u8) {
         let pc: u16 = self.registers.get_pc();
         self.push_d16(pc);
         self.registers.set_pc(offset as u16);
     }
 
+    // Subtract with carry A -= n + C
+    fn sbc(&mut self, n: u8) {
+        let a: u8 = self.registers.get_a();
+        let carry: u8 = self.registers.get_flag(Flag::C) as u8;
+        let value: i16 = a as i16 - n as i16 - carry as i16;
+        self.registers.set_a(value as u8);
+
+        self.registers.set_flag(Flag::Z, value as u8 == 0);
+        self.registers.set_flag(Flag::N, true);
+        self.registers.set_flag(Flag::H, ((a & 0x0F) as i16 - (n & 0x0F) as i16 - carry as i16) < 0);
+        self.registers.set_flag(Flag::C, value < 0);
+    }
+
+    // Set bit b in target
+    fn set(&mut self, b: u8, target: Target) {
+        let r: u8 = self.read_target(target);
+        self.write_target(target, r | (0x01 << b));
+    }
+
+    // Set carry flag
+    fn scf(&mut self) {
+        self.registers.set_flag(Flag::N, false);
+        self.registers.set_flag(Flag::H, false);
+        self.registers.set_flag(Flag::C, true);
+    }
+
+    // Flags shared by the rotate, shift and swap instructions
+    fn set_shift_flags(&mut self, value: u8, carry: bool) {
+        self.registers.set_flag(Flag::Z, value == 0);
+        self.registers.set_flag(Flag::N, false);
+        self.registers.set_flag(Flag::H, false);
+        self.registers.set_flag(Flag::C, carry);
+    }
+
+    // Shift target left into carry, bit 0 cleared
+    fn sla(&mut self, target: Target) {
+        let r: u8 = self.read_target(target);
+        let value: u8 = r << 1;
+        self.write_target(target, value);
+        self.set_shift_flags(value, r & 0x80 == 0x80);
+    }
+
+    // Shift target right into carry, bit 7 unchanged
+    fn sra(&mut self, target: Target) {
+        let r: u8 = self.read_target(target);
+        let value: u8 = (r >> 1) | (r & 0x80);
+        self.write_target(target, value);
+        self.set_shift_flags(value, r & 0x01 == 0x01);
+    }
+
+    // Shift target right into carry, bit 7 cleared
+    fn srl(&mut self, target: Target) {
+        let r: u8 = self.read_target(target);
+        let value: u8 = r >> 1;
+        self.write_target(target, value);
+        self.set_shift_flags(value, r & 0x01 == 0x01);
+    }
+
     // Stop CPU and LCD until a button is pressed
-    pub fn stop(&mut self) {
-        // Stop CPU and LCD until a button is pressed
-        return;
+    fn stop(&mut self) {
+        // STOP is followed by a padding byte
+        self.get_at_pc_incr();
+        // and resets the divider
+        self.timer.write(0xFF04, 0x00);
+        self.stopped = true;
+    }
+
+    // Subtract A -= n
+    fn sub(&mut self, n: u8) {
+        let a: u8 = self.registers.get_a();
+        let value = a.overflowing_sub(n);
+        self.registers.set_a(value.0);
+
+        self.registers.set_flag(Flag::Z, value.0 == 0);
+        self.registers.set_flag(Flag::N, true);
+        self.registers.set_flag(Flag::H, (a & 0x0F) < (n & 0x0F));
+        self.registers.set_flag(Flag::C, value.1);
     }
 
     // Swap the upper and lower 4 bits
-    pub fn swap(&mut self, reg: Reg8) {
-        let mut r: u8 = self.registers.get_reg_8(reg);
+    fn swap(&mut self, target: Target) {
+        let mut r: u8 = self.read_target(target);
         let r_bottom: u8 = r & 0x0F;
         r >>= 4;
         r |= r_bottom << 4;
-        self.registers.set_reg_8(reg, r);
+        self.write_target(target, r);
+        self.set_shift_flags(r, false);
     }
 
     // XOR n with A => A
-    pub fn xor(&mut self, n: u8) {
+    fn xor(&mut self, n: u8) {
         let value: u8 = self.registers.get_a() ^ n;
         self.registers.set_a(value);
 
@@ -1134,4 +1548,258 @@ impl Gameboy {
         self.registers.set_flag(Flag::H, false);
         self.registers.set_flag(Flag::C, false);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ROM only cartridge running program from 0x0100, handler at the VBlank vector 0x0040
+    fn gameboy_with_program(program: &[u8], handler: &[u8]) -> Gameboy {
+        let mut rom: Vec<u8> = vec![0u8; 0x8000];
+        rom[0x0100..0x0100 + program.len()].copy_from_slice(program);
+        rom[0x0040..0x0040 + handler.len()].copy_from_slice(handler);
+        let mut gameboy: Gameboy = Gameboy::from_rom_bytes(rom).unwrap();
+        gameboy.registers.set_sp(0xFFFE);
+        gameboy
+    }
+
+    fn run_to(gameboy: &mut Gameboy, pc: u16) {
+        for _ in 0..100 {
+            if gameboy.registers.get_pc() == pc {
+                return;
+            }
+            gameboy.step_instruction().unwrap();
+        }
+        panic!("PC never reached 0x{:04X}", pc);
+    }
+
+    // Run program to its end
+    fn run(program: &[u8]) -> Gameboy {
+        let mut gameboy: Gameboy = gameboy_with_program(program, &[]);
+        run_to(&mut gameboy, 0x0100 + program.len() as u16);
+        gameboy
+    }
+
+    #[test]
+    fn add_sets_half_carry() {
+        // LD A,0x0F; ADD A,0x01
+        let gameboy: Gameboy = run(&[0x3E, 0x0F, 0xC6, 0x01]);
+        assert_eq!(gameboy.registers.get_a(), 0x10);
+        assert_eq!(gameboy.registers.get_f(), 0x20);
+    }
+
+    #[test]
+    fn adc_adds_carry_into_half_carry() {
+        // LD A,0x0E; SCF; ADC A,0x01
+        let gameboy: Gameboy = run(&[0x3E, 0x0E, 0x37, 0xCE, 0x01]);
+        assert_eq!(gameboy.registers.get_a(), 0x10);
+        assert_eq!(gameboy.registers.get_f(), 0x20);
+
+        // LD A,0xFF; SCF; ADC A,0x00
+        let gameboy: Gameboy = run(&[0x3E, 0xFF, 0x37, 0xCE, 0x00]);
+        assert_eq!(gameboy.registers.get_a(), 0x00);
+        assert_eq!(gameboy.registers.get_f(), 0xB0);
+    }
+
+    #[test]
+    fn sbc_subtracts_carry_into_half_carry() {
+        // LD A,0x10; SCF; SBC A,0x00
+        let gameboy: Gameboy = run(&[0x3E, 0x10, 0x37, 0xDE, 0x00]);
+        assert_eq!(gameboy.registers.get_a(), 0x0F);
+        assert_eq!(gameboy.registers.get_f(), 0x60);
+
+        // LD A,0x00; SCF; SBC A,0xFF
+        let gameboy: Gameboy = run(&[0x3E, 0x00, 0x37, 0xDE, 0xFF]);
+        assert_eq!(gameboy.registers.get_a(), 0x00);
+        assert_eq!(gameboy.registers.get_f(), 0xF0);
+    }
+
+    #[test]
+    fn daa_adjusts_after_add() {
+        // LD A,0x45; ADD A,0x38; DAA
+        let gameboy: Gameboy = run(&[0x3E, 0x45, 0xC6, 0x38, 0x27]);
+        assert_eq!(gameboy.registers.get_a(), 0x83);
+        assert_eq!(gameboy.registers.get_f(), 0x00);
+
+        // LD A,0x99; ADD A,0x01; DAA
+        let gameboy: Gameboy = run(&[0x3E, 0x99, 0xC6, 0x01, 0x27]);
+        assert_eq!(gameboy.registers.get_a(), 0x00);
+        assert_eq!(gameboy.registers.get_f(), 0x90);
+    }
+
+    #[test]
+    fn daa_adjusts_after_sub() {
+        // LD A,0x42; SUB 0x08; DAA
+        let gameboy: Gameboy = run(&[0x3E, 0x42, 0xD6, 0x08, 0x27]);
+        assert_eq!(gameboy.registers.get_a(), 0x34);
+        assert_eq!(gameboy.registers.get_f(), 0x40);
+    }
+
+    #[test]
+    fn add_sp_r8_flags_come_from_the_low_byte() {
+        // LD SP,0x00FF; ADD SP,1
+        let gameboy: Gameboy = run(&[0x31, 0xFF, 0x00, 0xE8, 0x01]);
+        assert_eq!(gameboy.registers.get_sp(), 0x0100);
+        assert_eq!(gameboy.registers.get_f(), 0x30);
+
+        // LD SP,0x0000; ADD SP,-1
+        let gameboy: Gameboy = run(&[0x31, 0x00, 0x00, 0xE8, 0xFF]);
+        assert_eq!(gameboy.registers.get_sp(), 0xFFFF);
+        assert_eq!(gameboy.registers.get_f(), 0x00);
+    }
+
+    #[test]
+    fn cb_prefix_decodes_operation_bit_and_register() {
+        // LD B,0x80; RLC B
+        let gameboy: Gameboy = run(&[0x06, 0x80, 0xCB, 0x00]);
+        assert_eq!(gameboy.registers.get_b(), 0x01);
+        assert_eq!(gameboy.registers.get_f(), 0x10);
+
+        // LD A,0xF1; SWAP A
+        let gameboy: Gameboy = run(&[0x3E, 0xF1, 0xCB, 0x37]);
+        assert_eq!(gameboy.registers.get_a(), 0x1F);
+        assert_eq!(gameboy.registers.get_f(), 0x00);
+
+        // XOR A; BIT 7,A
+        let gameboy: Gameboy = run(&[0xAF, 0xCB, 0x7F]);
+        assert_eq!(gameboy.registers.get_f(), 0xA0);
+
+        // LD HL,0xC000; SET 3,(HL)
+        let gameboy: Gameboy = run(&[0x21, 0x00, 0xC0, 0xCB, 0xDE]);
+        assert_eq!(gameboy.read(0xC000), 0x08);
+    }
+
+    #[test]
+    fn illegal_opcode_locks_the_cpu() {
+        let mut gameboy: Gameboy = gameboy_with_program(&[0xD3, 0x04], &[]);
+        match gameboy.step_instruction() {
+            Err(EmulatorError::IllegalOpcode { opcode, addr }) => {
+                assert_eq!(opcode, 0xD3);
+                assert_eq!(addr, 0x0100);
+            }
+            _ => panic!("expected an illegal opcode error"),
+        }
+
+        // Reported once, after that the CPU only burns cycles
+        let cycles: u64 = gameboy.cycles();
+        for _ in 0..10 {
+            assert!(gameboy.step_instruction().is_ok());
+        }
+        assert_eq!(gameboy.registers.get_pc(), 0x0101);
+        assert_eq!(gameboy.registers.get_b(), Registers::new().get_b());
+        assert!(gameboy.cycles() > cycles);
+    }
+
+    #[test]
+    fn load_rom_creates_missing_save_dir() {
+        let dir: PathBuf = std::env::temp_dir().join(format!("gameboy-save-dir-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        // MBC1+RAM+BATTERY with 8KB of RAM
+        let mut rom: Vec<u8> = vec![0u8; 0x8000];
+        rom[0x0147] = 0x03;
+        rom[0x0149] = 0x02;
+        let rom_path: PathBuf = dir.join("game.gb");
+        fs::write(&rom_path, &rom).unwrap();
+
+        let mut gameboy: Gameboy = Gameboy::new();
+        gameboy.set_save_dir(dir.join("saves"));
+        gameboy.load_rom(&rom_path).unwrap();
+        assert!(dir.join("saves").is_dir());
+
+        gameboy.cartridge.write_rom(0x0000, 0x0A);
+        gameboy.cartridge.write_ram(0xA000, 0x42);
+        gameboy.save().unwrap();
+        assert_eq!(fs::read(dir.join("saves").join("game.sav")).unwrap()[0], 0x42);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn stop_waits_for_a_selected_button_not_the_joypad_flag() {
+        // LD A,0x10; LDH (IF),A; LD A,0x20; LDH (P1),A; STOP; INC B
+        let program: [u8; 11] = [0x3E, 0x10, 0xE0, 0x0F, 0x3E, 0x20, 0xE0, 0x00, 0x10, 0x00, 0x04];
+        let mut gameboy: Gameboy = gameboy_with_program(&program, &[]);
+        run_to(&mut gameboy, 0x010A);
+
+        // A stale joypad flag doesn't wake the CPU, but the cycles keep counting
+        let cycles: u64 = gameboy.cycles();
+        for _ in 0..10 {
+            gameboy.step_instruction().unwrap();
+        }
+        assert_eq!(gameboy.registers.get_pc(), 0x010A);
+        assert_eq!(gameboy.cycles(), cycles + 10);
+
+        // Action buttons aren't selected
+        gameboy.set_buttons(Button::A.mask());
+        gameboy.step_instruction().unwrap();
+        assert_eq!(gameboy.registers.get_pc(), 0x010A);
+
+        gameboy.set_buttons(Button::Right.mask());
+        gameboy.step_instruction().unwrap();
+        assert_eq!(gameboy.registers.get_pc(), 0x010B);
+        assert_eq!(gameboy.registers.get_b(), Registers::new().get_b().wrapping_add(1));
+    }
+
+    #[test]
+    fn oam_dma_copies_160_bytes_after_the_triggering_instruction() {
+        let mut gameboy: Gameboy = gameboy_with_program(&[], &[]);
+        for i in 0..0xA0 {
+            gameboy.bus_write(0xC000 + i, i as u8 + 1);
+        }
+
+        // LDH (0x46),A from HRAM, which stays reachable during the transfer
+        gameboy.bus_write(0xFF80, 0xE0);
+        gameboy.bus_write(0xFF81, 0x46);
+        gameboy.registers.set_a(0xC0);
+        gameboy.registers.set_pc(0xFF80);
+
+        // Nothing is copied during the LDH itself or the M-cycle after it
+        gameboy.step_instruction().unwrap();
+        assert!(!gameboy.dma.is_active());
+        gameboy.tick(1);
+        assert!(gameboy.dma.is_active());
+        assert_eq!(gameboy.ppu.read_oam(0xFE00), 0x00);
+
+        // One byte per M-cycle, the CPU only sees 0xFF outside HRAM meanwhile
+        gameboy.tick(0x9F);
+        assert_eq!(gameboy.read(0xC000), 0xFF);
+        assert_eq!(gameboy.read(0xFF81), 0x46);
+        assert_eq!(gameboy.ppu.read_oam(0xFE9E), 0x9F);
+        assert_eq!(gameboy.ppu.read_oam(0xFE9F), 0x00);
+
+        gameboy.tick(1);
+        assert!(!gameboy.dma.is_active());
+        assert_eq!(gameboy.read(0xC000), 0x01);
+        for i in 0..0xA0 {
+            assert_eq!(gameboy.ppu.read_oam(0xFE00 + i), i as u8 + 1);
+        }
+    }
+
+    #[test]
+    fn gameboy_is_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Gameboy>();
+    }
+
+    #[test]
+    fn ei_halt_with_pending_interrupt_returns_to_halt() {
+        // LD A,0x01; LDH (IE),A; LDH (IF),A; EI; HALT
+        let program: [u8; 8] = [0x3E, 0x01, 0xE0, 0xFF, 0xE0, 0x0F, 0xFB, 0x76];
+        // INC B; RETI
+        let handler: [u8; 2] = [0x04, 0xD9];
+        let mut gameboy: Gameboy = gameboy_with_program(&program, &handler);
+
+        run_to(&mut gameboy, 0x0040);
+        assert_eq!(gameboy.read(0xFFFC) as u16 | (gameboy.read(0xFFFD) as u16) << 8, 0x0107);
+
+        // The handler runs once and returns to the HALT
+        gameboy.step_instruction().unwrap();
+        assert_eq!(gameboy.registers.get_b(), 1);
+        gameboy.step_instruction().unwrap();
+        assert_eq!(gameboy.registers.get_pc(), 0x0107);
+        assert_eq!(gameboy.registers.get_b(), 1);
+    }
+}
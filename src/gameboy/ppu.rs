@@ -0,0 +1,610 @@
+use super::interrupts::*;
+
+/// LCD width in pixels
+pub const SCREEN_WIDTH: usize = 160;
+/// LCD height in pixels
+pub const SCREEN_HEIGHT: usize = 144;
+
+// Dots (4.194304 MHz) spent in each part of a scanline
+const OAM_SCAN_DOTS: u32 = 80;
+const PIXEL_TRANSFER_DOTS: u32 = 172;
+const LINE_DOTS: u32 = 456;
+const LINES: u8 = 154;
+
+// LCDC bits
+const LCD_ENABLE: u8 = 0x80;
+const WINDOW_TILE_MAP: u8 = 0x40;
+const WINDOW_ENABLE: u8 = 0x20;
+const BG_TILE_DATA: u8 = 0x10;
+const BG_TILE_MAP: u8 = 0x08;
+const OBJ_SIZE: u8 = 0x04;
+const OBJ_ENABLE: u8 = 0x02;
+const BG_ENABLE: u8 = 0x01;
+
+// OAM attribute bits
+const OBJ_BEHIND_BG: u8 = 0x80;
+const OBJ_Y_FLIP: u8 = 0x40;
+const OBJ_X_FLIP: u8 = 0x20;
+const OBJ_PALETTE: u8 = 0x10;
+
+// Hardware limit of objects drawn on one scanline
+const OBJS_PER_LINE: usize = 10;
+
+// One of the 40 OAM entries
+#[derive(Copy, Clone)]
+struct Sprite {
+    index: usize,
+    y: i32,
+    x: i32,
+    tile: u8,
+    attributes: u8,
+}
+
+// STAT mode bits
+#[derive(Copy, Clone, PartialEq)]
+pub enum Mode {
+    HBlank = 0,
+    VBlank = 1,
+    OamScan = 2,
+    PixelTransfer = 3,
+}
+
+pub struct Ppu {
+    vram: [u8; 0x2000],
+    oam: [u8; 0xA0],
+    lcdc: u8, // 0xFF40
+    stat: u8, // 0xFF41, interrupt select bits only
+    scy: u8,  // 0xFF42
+    scx: u8,  // 0xFF43
+    ly: u8,   // 0xFF44
+    lyc: u8,  // 0xFF45
+    bgp: u8,  // 0xFF47
+    obp0: u8, // 0xFF48
+    obp1: u8, // 0xFF49
+    wy: u8,   // 0xFF4A
+    wx: u8,   // 0xFF4B
+    mode: Mode,
+    dots: u32,
+    stat_line: bool,
+    // Window lines drawn so far this frame and whether LY has matched WY
+    window_line: u8,
+    window_triggered: bool,
+    // Shade (0-3) of every pixel, the back buffer is drawn into line by line
+    back_buffer: [u8; SCREEN_WIDTH * SCREEN_HEIGHT],
+    frame_buffer: [u8; SCREEN_WIDTH * SCREEN_HEIGHT],
+}
+
+impl Ppu {
+    pub fn new() -> Ppu {
+        Ppu {
+            vram: [0u8; 0x2000],
+            oam: [0u8; 0xA0],
+            lcdc: 0x91,
+            stat: 0x00,
+            scy: 0x00,
+            scx: 0x00,
+            ly: 0x00,
+            lyc: 0x00,
+            bgp: 0xFC,
+            obp0: 0xFF,
+            obp1: 0xFF,
+            wy: 0x00,
+            wx: 0x00,
+            mode: Mode::OamScan,
+            dots: 0,
+            stat_line: false,
+            window_line: 0,
+            window_triggered: false,
+            back_buffer: [0u8; SCREEN_WIDTH * SCREEN_HEIGHT],
+            frame_buffer: [0u8; SCREEN_WIDTH * SCREEN_HEIGHT],
+        }
+    }
+
+    // Last completed frame
+    pub fn frame_buffer(&self) -> &[u8; SCREEN_WIDTH * SCREEN_HEIGHT] {
+        &self.frame_buffer
+    }
+
+    pub fn read_vram(&self, addr: u16) -> u8 {
+        self.vram[addr as usize - 0x8000]
+    }
+    pub fn write_vram(&mut self, addr: u16, d8: u8) {
+        self.vram[addr as usize - 0x8000] = d8;
+    }
+
+    pub fn read_oam(&self, addr: u16) -> u8 {
+        self.oam[addr as usize - 0xFE00]
+    }
+    pub fn write_oam(&mut self, addr: u16, d8: u8) {
+        self.oam[addr as usize - 0xFE00] = d8;
+    }
+
+    // 0xFEA0-0xFEFF isn't backed by memory, on DMG it reads 0x00 unless
+    // the PPU is using OAM in which case it reads 0xFF
+    pub fn read_prohibited(&self) -> u8 {
+        match self.mode {
+            Mode::OamScan | Mode::PixelTransfer if self.lcdc & LCD_ENABLE == LCD_ENABLE => 0xFF,
+            _ => 0x00,
+        }
+    }
+
+    pub fn read(&self, addr: u16) -> u8 {
+        match addr {
+            0xFF40 => self.lcdc,
+            0xFF41 => {
+                let coincidence: u8 = ((self.ly == self.lyc) as u8) << 2;
+                0x80 | self.stat | coincidence | self.mode as u8
+            }
+            0xFF42 => self.scy,
+            0xFF43 => self.scx,
+            0xFF44 => self.ly,
+            0xFF45 => self.lyc,
+            0xFF47 => self.bgp,
+            0xFF48 => self.obp0,
+            0xFF49 => self.obp1,
+            0xFF4A => self.wy,
+            0xFF4B => self.wx,
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write(&mut self, addr: u16, d8: u8, interrupts: &mut Interrupts) {
+        match addr {
+            0xFF40 => {
+                if self.lcdc & LCD_ENABLE == LCD_ENABLE && d8 & LCD_ENABLE == 0 {
+                    // Turning the LCD off resets LY and blanks the screen
+                    self.ly = 0;
+                    self.dots = 0;
+                    self.mode = Mode::HBlank;
+                    self.window_line = 0;
+                    self.window_triggered = false;
+                    self.back_buffer = [0u8; SCREEN_WIDTH * SCREEN_HEIGHT];
+                    self.frame_buffer = self.back_buffer;
+                } else if self.lcdc & LCD_ENABLE == 0 && d8 & LCD_ENABLE == LCD_ENABLE {
+                    // and turning it back on starts a new frame from line 0
+                    self.mode = Mode::OamScan;
+                }
+                self.lcdc = d8;
+            }
+            0xFF41 => self.stat = d8 & 0x78,
+            0xFF42 => self.scy = d8,
+            0xFF43 => self.scx = d8,
+            // LY is read only
+            0xFF44 => {}
+            0xFF45 => self.lyc = d8,
+            0xFF47 => self.bgp = d8,
+            0xFF48 => self.obp0 = d8,
+            0xFF49 => self.obp1 = d8,
+            0xFF4A => self.wy = d8,
+            0xFF4B => self.wx = d8,
+            _ => {}
+        }
+        self.update_stat_line(interrupts);
+    }
+
+    // Advance by M-cycles, walking OAM scan -> pixel transfer -> HBlank for
+    // every visible line followed by 10 lines of VBlank
+    pub fn tick(&mut self, cycles: u32, interrupts: &mut Interrupts) {
+        if self.lcdc & LCD_ENABLE == 0 {
+            return;
+        }
+
+        for _ in 0..cycles * 4 {
+            self.dots += 1;
+
+            match self.mode {
+                Mode::OamScan => {
+                    if self.dots == OAM_SCAN_DOTS {
+                        self.mode = Mode::PixelTransfer;
+                    }
+                }
+                Mode::PixelTransfer => {
+                    if self.dots == OAM_SCAN_DOTS + PIXEL_TRANSFER_DOTS {
+                        self.render_scanline();
+                        self.mode = Mode::HBlank;
+                    }
+                }
+                Mode::HBlank | Mode::VBlank => {
+                    if self.dots == LINE_DOTS {
+                        self.dots = 0;
+                        self.ly = (self.ly + 1) % LINES;
+
+                        if self.ly == 0 {
+                            self.mode = Mode::OamScan;
+                            self.window_line = 0;
+                            self.window_triggered = false;
+                        } else if self.ly == SCREEN_HEIGHT as u8 {
+                            self.mode = Mode::VBlank;
+                            self.frame_buffer = self.back_buffer;
+                            interrupts.request(Interrupt::VBlank);
+                        } else if self.mode == Mode::HBlank {
+                            self.mode = Mode::OamScan;
+                        }
+                    }
+                }
+            }
+
+            self.update_stat_line(interrupts);
+        }
+    }
+
+    // The STAT interrupt fires on the rising edge of the OR of all enabled sources
+    fn update_stat_line(&mut self, interrupts: &mut Interrupts) {
+        let line: bool = (self.stat & 0x40 == 0x40 && self.ly == self.lyc)
+            || (self.stat & 0x20 == 0x20 && self.mode == Mode::OamScan)
+            || (self.stat & 0x10 == 0x10 && self.mode == Mode::VBlank)
+            || (self.stat & 0x08 == 0x08 && self.mode == Mode::HBlank);
+
+        if line && !self.stat_line {
+            interrupts.request(Interrupt::Stat);
+        }
+        self.stat_line = line;
+    }
+
+    // Offset into VRAM of a tile, LCDC bit 4 selects unsigned tile numbers from
+    // 0x8000 or signed tile numbers from 0x9000
+    fn tile_data_addr(&self, tile_number: u8) -> usize {
+        if self.lcdc & BG_TILE_DATA == BG_TILE_DATA {
+            tile_number as usize * 16
+        } else {
+            (0x1000 + (tile_number as i8 as i32) * 16) as usize
+        }
+    }
+
+    // Colour index of pixel x (0 is leftmost) of the 2 byte tile line at line_addr
+    fn tile_line_pixel(&self, line_addr: usize, x: usize) -> u8 {
+        let lsb: u8 = self.vram[line_addr];
+        let msb: u8 = self.vram[line_addr + 1];
+
+        let bit: usize = 7 - x;
+        ((lsb >> bit) & 0x01) | (((msb >> bit) & 0x01) << 1)
+    }
+
+    // Colour index of pixel (x, y) within the 256x256 map at map_addr
+    fn tile_map_pixel(&self, map_addr: usize, x: usize, y: usize) -> u8 {
+        let tile_number: u8 = self.vram[map_addr + (y / 8) * 32 + (x / 8)];
+        let line_addr: usize = self.tile_data_addr(tile_number) + (y % 8) * 2;
+        self.tile_line_pixel(line_addr, x % 8)
+    }
+
+    // The first 10 objects in OAM overlapping LY, in drawing priority order
+    fn scanline_sprites(&self, height: i32) -> Vec<Sprite> {
+        let ly: i32 = self.ly as i32;
+
+        let mut sprites: Vec<Sprite> = self
+            .oam
+            .chunks(4)
+            .enumerate()
+            .map(|(index, entry)| Sprite {
+                index,
+                y: entry[0] as i32 - 16,
+                x: entry[1] as i32 - 8,
+                tile: entry[2],
+                attributes: entry[3],
+            })
+            .filter(|sprite| ly >= sprite.y && ly < sprite.y + height)
+            .take(OBJS_PER_LINE)
+            .collect();
+
+        // On DMG the object with the smaller X wins, then the one earlier in OAM
+        sprites.sort_by_key(|sprite| (sprite.x, sprite.index));
+        sprites
+    }
+
+    // Draw the objects on line LY over the background colour indices in bg_line
+    fn render_sprites(&self, bg_line: &[u8; SCREEN_WIDTH], line: &mut [u8; SCREEN_WIDTH]) {
+        let height: i32 = if self.lcdc & OBJ_SIZE == OBJ_SIZE { 16 } else { 8 };
+        // Pixels already claimed by a higher priority object
+        let mut drawn: [bool; SCREEN_WIDTH] = [false; SCREEN_WIDTH];
+
+        for sprite in self.scanline_sprites(height) {
+            let mut sprite_y: i32 = self.ly as i32 - sprite.y;
+            if sprite.attributes & OBJ_Y_FLIP == OBJ_Y_FLIP {
+                sprite_y = height - 1 - sprite_y;
+            }
+
+            // 8x16 objects ignore bit 0 of the tile number, objects always use 0x8000 addressing
+            let tile: u8 = if height == 16 { sprite.tile & 0xFE } else { sprite.tile };
+            let line_addr: usize = tile as usize * 16 + sprite_y as usize * 2;
+            let palette: u8 = if sprite.attributes & OBJ_PALETTE == OBJ_PALETTE { self.obp1 } else { self.obp0 };
+
+            for sprite_x in 0..8 {
+                let x: i32 = sprite.x + sprite_x;
+                if x < 0 || x >= SCREEN_WIDTH as i32 || drawn[x as usize] {
+                    continue;
+                }
+
+                let pixel_x: usize = if sprite.attributes & OBJ_X_FLIP == OBJ_X_FLIP { 7 - sprite_x as usize } else { sprite_x as usize };
+                let color_data: u8 = self.tile_line_pixel(line_addr, pixel_x);
+                // Colour 0 is transparent
+                if color_data == 0 {
+                    continue;
+                }
+                drawn[x as usize] = true;
+
+                // Objects behind the background only show over background colour 0
+                if sprite.attributes & OBJ_BEHIND_BG == OBJ_BEHIND_BG && bg_line[x as usize] != 0 {
+                    continue;
+                }
+                line[x as usize] = apply_palette(palette, color_data);
+            }
+        }
+    }
+
+    // Draw line LY into the back buffer
+    fn render_scanline(&mut self) {
+        let y: usize = self.ly as usize;
+        let mut line: [u8; SCREEN_WIDTH] = [0u8; SCREEN_WIDTH];
+
+        // With the background disabled it is drawn as colour 0
+        if self.lcdc & BG_ENABLE == BG_ENABLE {
+            let map_addr: usize = if self.lcdc & BG_TILE_MAP == BG_TILE_MAP { 0x1C00 } else { 0x1800 };
+            let bg_y: usize = (y + self.scy as usize) & 0xFF;
+
+            for (x, pixel) in line.iter_mut().enumerate() {
+                let bg_x: usize = (x + self.scx as usize) & 0xFF;
+                *pixel = self.tile_map_pixel(map_addr, bg_x, bg_y);
+            }
+        }
+
+        // The window only starts on lines after LY has matched WY this frame
+        if self.ly == self.wy {
+            self.window_triggered = true;
+        }
+
+        // The background enable bit also hides the window on DMG
+        let window_visible: bool = self.lcdc & WINDOW_ENABLE == WINDOW_ENABLE
+            && self.lcdc & BG_ENABLE == BG_ENABLE
+            && self.window_triggered
+            && self.wx < 167;
+
+        if window_visible {
+            let map_addr: usize = if self.lcdc & WINDOW_TILE_MAP == WINDOW_TILE_MAP { 0x1C00 } else { 0x1800 };
+            let window_y: usize = self.window_line as usize;
+
+            // WX holds the window's screen x position + 7
+            let start_x: usize = (self.wx as usize).saturating_sub(7);
+            for (x, pixel) in line.iter_mut().enumerate().skip(start_x) {
+                let window_x: usize = x + 7 - self.wx as usize;
+                *pixel = self.tile_map_pixel(map_addr, window_x, window_y);
+            }
+
+            // The window line counter only advances on lines the window was drawn
+            self.window_line += 1;
+        }
+
+        // Keep the raw colour indices for object priority, then map them through BGP.
+        // A disabled background is always white rather than BGP colour 0
+        let bg_line: [u8; SCREEN_WIDTH] = line;
+        if self.lcdc & BG_ENABLE == BG_ENABLE {
+            for pixel in line.iter_mut() {
+                *pixel = apply_palette(self.bgp, *pixel);
+            }
+        }
+
+        if self.lcdc & OBJ_ENABLE == OBJ_ENABLE {
+            self.render_sprites(&bg_line, &mut line);
+        }
+
+        self.back_buffer[y * SCREEN_WIDTH..(y + 1) * SCREEN_WIDTH].copy_from_slice(&line);
+    }
+}
+
+// Map a colour index through a BGP/OBP palette register to a shade
+fn apply_palette(palette: u8, color_data: u8) -> u8 {
+    (palette >> (color_data * 2)) & 0x03
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VBLANK: u8 = 0x01;
+    const STAT: u8 = 0x02;
+
+    fn mode(ppu: &Ppu) -> u8 {
+        ppu.read(0xFF41) & 0x03
+    }
+
+    #[test]
+    fn scanline_modes_take_80_172_and_204_dots() {
+        let mut ppu: Ppu = Ppu::new();
+        let mut interrupts: Interrupts = Interrupts::new();
+        assert_eq!(mode(&ppu), Mode::OamScan as u8);
+
+        // 4 dots per M-cycle
+        ppu.tick(19, &mut interrupts);
+        assert_eq!(mode(&ppu), Mode::OamScan as u8);
+        ppu.tick(1, &mut interrupts);
+        assert_eq!(mode(&ppu), Mode::PixelTransfer as u8);
+
+        ppu.tick(42, &mut interrupts);
+        assert_eq!(mode(&ppu), Mode::PixelTransfer as u8);
+        ppu.tick(1, &mut interrupts);
+        assert_eq!(mode(&ppu), Mode::HBlank as u8);
+
+        ppu.tick(50, &mut interrupts);
+        assert_eq!(mode(&ppu), Mode::HBlank as u8);
+        assert_eq!(ppu.read(0xFF44), 0);
+        ppu.tick(1, &mut interrupts);
+        assert_eq!(mode(&ppu), Mode::OamScan as u8);
+        assert_eq!(ppu.read(0xFF44), 1);
+    }
+
+    #[test]
+    fn vblank_starts_at_line_144() {
+        let mut ppu: Ppu = Ppu::new();
+        let mut interrupts: Interrupts = Interrupts::new();
+        interrupts.set_if(0x00);
+
+        ppu.tick(114 * 144 - 1, &mut interrupts);
+        assert_eq!(ppu.read(0xFF44), 143);
+        assert_eq!(interrupts.get_if() & VBLANK, 0);
+
+        ppu.tick(1, &mut interrupts);
+        assert_eq!(ppu.read(0xFF44), 144);
+        assert_eq!(mode(&ppu), Mode::VBlank as u8);
+        assert_eq!(interrupts.get_if() & VBLANK, VBLANK);
+
+        // 10 lines of VBlank then back to line 0
+        ppu.tick(114 * 10, &mut interrupts);
+        assert_eq!(ppu.read(0xFF44), 0);
+        assert_eq!(mode(&ppu), Mode::OamScan as u8);
+    }
+
+    #[test]
+    fn stat_interrupt_fires_on_the_rising_edge_only() {
+        let mut ppu: Ppu = Ppu::new();
+        let mut interrupts: Interrupts = Interrupts::new();
+
+        // HBlank and OAM scan sources, already high in OAM scan
+        ppu.write(0xFF41, 0x28, &mut interrupts);
+        assert_eq!(interrupts.get_if() & STAT, STAT);
+        interrupts.set_if(0x00);
+
+        // Low during pixel transfer, rises again in HBlank
+        ppu.tick(63, &mut interrupts);
+        assert_eq!(interrupts.get_if() & STAT, STAT);
+        interrupts.set_if(0x00);
+
+        // HBlank straight into OAM scan keeps the line high
+        ppu.tick(51, &mut interrupts);
+        assert_eq!(mode(&ppu), Mode::OamScan as u8);
+        assert_eq!(interrupts.get_if() & STAT, 0);
+    }
+
+    #[test]
+    fn lyc_coincidence_sets_stat_bit_and_interrupt() {
+        let mut ppu: Ppu = Ppu::new();
+        let mut interrupts: Interrupts = Interrupts::new();
+        ppu.write(0xFF45, 2, &mut interrupts);
+        ppu.write(0xFF41, 0x40, &mut interrupts);
+        assert_eq!(ppu.read(0xFF41) & 0x04, 0);
+
+        ppu.tick(114 * 2 - 1, &mut interrupts);
+        assert_eq!(interrupts.get_if() & STAT, 0);
+        ppu.tick(1, &mut interrupts);
+        assert_eq!(ppu.read(0xFF41) & 0x04, 0x04);
+        assert_eq!(interrupts.get_if() & STAT, STAT);
+
+        ppu.tick(114, &mut interrupts);
+        assert_eq!(ppu.read(0xFF41) & 0x04, 0);
+    }
+
+    #[test]
+    fn lcd_off_resets_ly_and_blanks_the_screen() {
+        let mut ppu: Ppu = Ppu::new();
+        let mut interrupts: Interrupts = Interrupts::new();
+        ppu.frame_buffer[0] = 3;
+        ppu.tick(114 * 5 + 30, &mut interrupts);
+        assert_eq!(ppu.read(0xFF44), 5);
+
+        ppu.write(0xFF40, 0x11, &mut interrupts);
+        assert_eq!(ppu.read(0xFF44), 0);
+        assert_eq!(mode(&ppu), Mode::HBlank as u8);
+        assert_eq!(ppu.frame_buffer()[0], 0);
+
+        // Nothing runs while off
+        ppu.tick(114 * 3, &mut interrupts);
+        assert_eq!(ppu.read(0xFF44), 0);
+
+        // and turning it on starts line 0 from OAM scan
+        ppu.write(0xFF40, 0x91, &mut interrupts);
+        assert_eq!(mode(&ppu), Mode::OamScan as u8);
+        ppu.tick(114, &mut interrupts);
+        assert_eq!(ppu.read(0xFF44), 1);
+    }
+
+    // LCD on with BG and objects from 0x8000 through identity palettes,
+    // tiles 1-3 are solid colours 1-3
+    fn render_ppu() -> Ppu {
+        let mut ppu: Ppu = Ppu::new();
+        ppu.lcdc = LCD_ENABLE | BG_TILE_DATA | OBJ_ENABLE | BG_ENABLE;
+        ppu.bgp = 0xE4;
+        ppu.obp0 = 0xE4;
+        for row in 0..8 {
+            set_tile_row(&mut ppu, 1, row, 0xFF, 0x00);
+            set_tile_row(&mut ppu, 2, row, 0x00, 0xFF);
+            set_tile_row(&mut ppu, 3, row, 0xFF, 0xFF);
+        }
+        ppu
+    }
+
+    fn set_tile_row(ppu: &mut Ppu, tile: usize, row: usize, lsb: u8, msb: u8) {
+        ppu.vram[tile * 16 + row * 2] = lsb;
+        ppu.vram[tile * 16 + row * 2 + 1] = msb;
+    }
+
+    // OAM entry at screen position (x, y)
+    fn set_sprite(ppu: &mut Ppu, index: usize, x: i32, y: i32, tile: u8, attributes: u8) {
+        let entry: [u8; 4] = [(y + 16) as u8, (x + 8) as u8, tile, attributes];
+        ppu.oam[index * 4..index * 4 + 4].copy_from_slice(&entry);
+    }
+
+    fn render_line(ppu: &mut Ppu, ly: u8) -> Vec<u8> {
+        ppu.ly = ly;
+        ppu.render_scanline();
+        let y: usize = ly as usize;
+        ppu.back_buffer[y * SCREEN_WIDTH..(y + 1) * SCREEN_WIDTH].to_vec()
+    }
+
+    #[test]
+    fn only_the_first_10_objects_in_oam_are_drawn() {
+        let mut ppu: Ppu = render_ppu();
+        for index in 0..OBJS_PER_LINE {
+            set_sprite(&mut ppu, index, 40 + index as i32 * 8, 0, 1, 0x00);
+        }
+        // The 11th has the smallest X but is past the limit
+        set_sprite(&mut ppu, OBJS_PER_LINE, 0, 0, 1, 0x00);
+
+        let line: Vec<u8> = render_line(&mut ppu, 0);
+        assert_eq!(&line[0..8], &[0; 8]);
+        assert_eq!(&line[40..120], &[1; 80][..]);
+    }
+
+    #[test]
+    fn objects_behind_bg_only_show_over_colour_0() {
+        let mut ppu: Ppu = render_ppu();
+        // Background tile 0 has colour 1 in its left half
+        set_tile_row(&mut ppu, 0, 0, 0xF0, 0x00);
+        set_sprite(&mut ppu, 0, 0, 0, 2, OBJ_BEHIND_BG);
+
+        let line: Vec<u8> = render_line(&mut ppu, 0);
+        assert_eq!(&line[0..8], &[1, 1, 1, 1, 2, 2, 2, 2]);
+    }
+
+    #[test]
+    fn tall_objects_ignore_tile_bit_0_and_flip_both_tiles() {
+        let mut ppu: Ppu = render_ppu();
+        ppu.lcdc |= OBJ_SIZE;
+        // Tile 3 selects tiles 2 (top) and 3 (bottom), flipped vertically
+        set_sprite(&mut ppu, 0, 0, 0, 3, OBJ_Y_FLIP);
+
+        assert_eq!(&render_line(&mut ppu, 0)[0..8], &[3; 8]);
+        assert_eq!(&render_line(&mut ppu, 15)[0..8], &[2; 8]);
+        assert_eq!(&render_line(&mut ppu, 16)[0..8], &[0; 8]);
+    }
+
+    #[test]
+    fn colour_0_of_an_object_is_transparent() {
+        let mut ppu: Ppu = render_ppu();
+        // Tile 4 has colour 1 in its left half and colour 0 in its right half
+        set_tile_row(&mut ppu, 4, 0, 0xF0, 0x00);
+        // Same X, so the earlier entry wins where it isn't transparent
+        set_sprite(&mut ppu, 0, 0, 0, 4, 0x00);
+        set_sprite(&mut ppu, 1, 0, 0, 2, 0x00);
+
+        let line: Vec<u8> = render_line(&mut ppu, 0);
+        assert_eq!(&line[0..8], &[1, 1, 1, 1, 2, 2, 2, 2]);
+    }
+
+    #[test]
+    fn smaller_x_wins_over_oam_order() {
+        let mut ppu: Ppu = render_ppu();
+        set_sprite(&mut ppu, 0, 4, 0, 3, 0x00);
+        set_sprite(&mut ppu, 1, 0, 0, 2, 0x00);
+
+        let line: Vec<u8> = render_line(&mut ppu, 0);
+        assert_eq!(&line[0..12], &[2, 2, 2, 2, 2, 2, 2, 2, 3, 3, 3, 3]);
+    }
+}
@@ -0,0 +1,61 @@
+// M-cycles to shift one bit at the 8192 Hz internal clock
+const BIT_CYCLES: u32 = 128;
+
+// Serial port, with no link cable attached every received bit is 1
+pub struct Serial {
+    data: u8,    // SB 0xFF01
+    control: u8, // SC 0xFF02
+    cycles: u32,
+    bits: u8,
+}
+
+impl Serial {
+    pub fn new() -> Serial {
+        Serial {
+            data: 0x00,
+            control: 0x7E,
+            cycles: 0,
+            bits: 0,
+        }
+    }
+
+    pub fn read(&self, addr: u16) -> u8 {
+        match addr {
+            0xFF01 => self.data,
+            _ => self.control | 0x7E,
+        }
+    }
+
+    pub fn write(&mut self, addr: u16, d8: u8) {
+        match addr {
+            0xFF01 => self.data = d8,
+            _ => {
+                self.control = d8;
+                self.cycles = 0;
+                self.bits = 0;
+            }
+        }
+    }
+
+    // Advance an internally clocked transfer, returns true when a byte has finished
+    pub fn tick(&mut self, cycles: u32) -> bool {
+        // Transfer in progress using the internal clock
+        if self.control & 0x81 != 0x81 {
+            return false;
+        }
+
+        self.cycles += cycles;
+        while self.cycles >= BIT_CYCLES {
+            self.cycles -= BIT_CYCLES;
+            self.data = (self.data << 1) | 0x01;
+            self.bits += 1;
+
+            if self.bits == 8 {
+                self.bits = 0;
+                self.control &= 0x7F;
+                return true;
+            }
+        }
+        false
+    }
+}
@@ -0,0 +1,124 @@
+// DIV/TIMA/TMA/TAC timer driven by the 16bit system counter
+pub struct Timer {
+    divider: u16, // DIV 0xFF04 is the upper 8 bits
+    tima: u8,     // 0xFF05
+    tma: u8,      // 0xFF06
+    tac: u8,      // 0xFF07
+    reload_pending: bool,
+}
+
+impl Timer {
+    pub fn new() -> Timer {
+        Timer {
+            divider: 0xABCC,
+            tima: 0x00,
+            tma: 0x00,
+            tac: 0xF8,
+            reload_pending: false,
+        }
+    }
+
+    pub fn read(&self, addr: u16) -> u8 {
+        match addr {
+            0xFF04 => (self.divider >> 8) as u8,
+            0xFF05 => self.tima,
+            0xFF06 => self.tma,
+            _ => self.tac | 0xF8,
+        }
+    }
+
+    pub fn write(&mut self, addr: u16, d8: u8) {
+        let signal: bool = self.signal();
+
+        match addr {
+            0xFF04 => self.divider = 0,
+            0xFF05 => {
+                // Writing TIMA in the cycle after an overflow cancels the reload
+                self.tima = d8;
+                self.reload_pending = false;
+            }
+            0xFF06 => self.tma = d8,
+            _ => self.tac = d8 & 0x07,
+        }
+
+        // Resetting DIV or changing TAC can produce a falling edge on its own
+        if signal && !self.signal() {
+            self.increment();
+        }
+    }
+
+    // Advance by M-cycles, returns true when the timer interrupt should be requested
+    pub fn tick(&mut self, cycles: u32) -> bool {
+        let mut interrupt: bool = false;
+
+        for _ in 0..cycles {
+            // TMA is loaded and the interrupt raised one M-cycle after TIMA overflows
+            if self.reload_pending {
+                self.reload_pending = false;
+                self.tima = self.tma;
+                interrupt = true;
+            }
+
+            let signal: bool = self.signal();
+            self.divider = self.divider.wrapping_add(4);
+            if signal && !self.signal() {
+                self.increment();
+            }
+        }
+        interrupt
+    }
+
+    // Divider bit selected by TAC ANDed with the timer enable bit
+    fn signal(&self) -> bool {
+        let bit: u8 = match self.tac & 0x03 {
+            0b00 => 9, // 4096 Hz
+            0b01 => 3, // 262144 Hz
+            0b10 => 5, // 65536 Hz
+            _ => 7,    // 16384 Hz
+        };
+        self.tac & 0x04 == 0x04 && (self.divider >> bit) & 0x01 == 0x01
+    }
+
+    fn increment(&mut self) {
+        let (tima, overflow) = self.tima.overflowing_add(1);
+        self.tima = tima;
+        if overflow {
+            self.reload_pending = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // TIMA at 0xFF counting every 4 M-cycles from a reset divider
+    fn overflowing_timer() -> Timer {
+        let mut timer: Timer = Timer::new();
+        timer.write(0xFF04, 0x00);
+        timer.write(0xFF06, 0x42);
+        timer.write(0xFF05, 0xFF);
+        timer.write(0xFF07, 0x05);
+        timer
+    }
+
+    #[test]
+    fn tima_reloads_one_cycle_after_overflow() {
+        let mut timer: Timer = overflowing_timer();
+        assert!(!timer.tick(4));
+        assert_eq!(timer.read(0xFF05), 0x00);
+
+        assert!(timer.tick(1));
+        assert_eq!(timer.read(0xFF05), 0x42);
+    }
+
+    #[test]
+    fn writing_tima_after_overflow_cancels_reload() {
+        let mut timer: Timer = overflowing_timer();
+        assert!(!timer.tick(4));
+        timer.write(0xFF05, 0x10);
+
+        assert!(!timer.tick(1));
+        assert_eq!(timer.read(0xFF05), 0x10);
+    }
+}
@@ -0,0 +1,28 @@
+//! A DMG Game Boy emulator core.
+//!
+//! [`Gameboy`] owns no window, input or audio device, so it can be embedded in
+//! tools, bots and frontends. The `sdl` feature (on by default) adds the SDL2
+//! frontend used by the `gameboy` binary in [`frontend`].
+//!
+//! ```no_run
+//! use gameboy::{Button, Gameboy};
+//!
+//! # fn main() -> Result<(), gameboy::EmulatorError> {
+//! let rom: Vec<u8> = std::fs::read("game.gb")?;
+//! let mut gameboy: Gameboy = Gameboy::from_rom_bytes(rom)?;
+//!
+//! gameboy.set_buttons(Button::Start.mask());
+//! gameboy.run_frame()?;
+//!
+//! let shades: &[u8] = gameboy.frame_buffer();
+//! let samples: Vec<f32> = gameboy.audio_samples();
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod frontend;
+mod gameboy;
+
+pub use gameboy::{
+    Button, CartridgeHeader, CgbSupport, EmulatorError, Gameboy, SAMPLE_RATE, SCREEN_HEIGHT, SCREEN_WIDTH,
+};
@@ -1,11 +1,286 @@
-mod gameboy;
+#[cfg(feature = "sdl")]
+use std::path::Path;
 
-fn main() -> Result<(), String> {
-    let mut gameboy = gameboy::Gameboy::new()?;
-    match gameboy.load_rom("./roms/testRom.gb".to_string()) {
-        Ok(_t) => {},
-        Err(_t) => return Err("Error loading rom".to_string()),
+use gameboy::frontend::{Frontend, NullFrontend};
+#[cfg(feature = "sdl")]
+use gameboy::frontend::{Bindings, Palette, SdlWindow};
+use gameboy::{CartridgeHeader, EmulatorError, Gameboy};
+
+const USAGE: &str = "\
+Usage: gameboy [OPTIONS] <ROM>
+       gameboy info <ROM>
+
+Options:
+  --boot-rom <FILE>   Run a 256 byte DMG boot ROM before the cartridge
+  --scale <N>         Window size as a multiple of 160x144 [default: 3]
+  --palette <NAME>    dmg, pocket, light or a palette config file
+  --volume <N>        Audio volume from 0 to 100 [default: 100]
+  --save-dir <DIR>    Directory for .sav files [default: next to the ROM]
+  --headless          Run without a window as fast as possible
+  --frames <N>        Stop after N frames
+  --cycles <N>        Stop after N M-cycles
+  -h, --help          Print this help
+
+Commands:
+  info <ROM>          Print the cartridge header of ROM
+
+Keys:
+  P                   Cycle the built-in palettes
+  1-4                 Mute or unmute sound channels 1-4
+  - / =               Volume down / up";
+
+// Flush battery backed RAM roughly every 5 seconds
+const SAVE_INTERVAL_FRAMES: u32 = 300;
+
+// The window options are only read by the SDL frontend
+#[cfg_attr(not(feature = "sdl"), allow(dead_code))]
+struct Options {
+    rom: String,
+    boot_rom: Option<String>,
+    scale: u32,
+    palette: Option<String>,
+    volume: u32,
+    save_dir: Option<String>,
+    headless: bool,
+    frames: Option<u32>,
+    cycles: Option<u64>,
+}
+
+impl Options {
+    // None when --help was asked for
+    fn parse(args: &[String]) -> Result<Option<Options>, EmulatorError> {
+        let mut rom: Option<String> = None;
+        let mut options = Options {
+            rom: String::new(),
+            boot_rom: None,
+            scale: 3,
+            palette: None,
+            volume: 100,
+            save_dir: None,
+            headless: false,
+            frames: None,
+            cycles: None,
+        };
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "--headless" => options.headless = true,
+                "--boot-rom" => options.boot_rom = Some(value(arg, args.next())?),
+                "--palette" => options.palette = Some(value(arg, args.next())?),
+                "--save-dir" => options.save_dir = Some(value(arg, args.next())?),
+                "--scale" => options.scale = number(arg, args.next())?,
+                "--volume" => options.volume = number(arg, args.next())?,
+                "--frames" => options.frames = Some(number(arg, args.next())?),
+                "--cycles" => options.cycles = Some(number(arg, args.next())?),
+                _ if arg.starts_with('-') => return Err(usage_error(format!("unknown option {}", arg))),
+                _ if rom.is_none() => rom = Some(arg.clone()),
+                _ => return Err(usage_error(format!("unexpected argument {}", arg))),
+            }
+        }
+
+        if options.scale == 0 {
+            return Err(usage_error("--scale must be at least 1".to_string()));
+        }
+        if options.volume > 100 {
+            return Err(usage_error("--volume must be between 0 and 100".to_string()));
+        }
+        match rom {
+            Some(rom) => options.rom = rom,
+            None => return Err(usage_error("no ROM given".to_string())),
+        }
+        Ok(Some(options))
+    }
+}
+
+fn usage_error(message: String) -> EmulatorError {
+    EmulatorError::Config(format!("{}\n\n{}", message, USAGE))
+}
+
+fn value(option: &str, value: Option<&String>) -> Result<String, EmulatorError> {
+    match value {
+        Some(value) => Ok(value.clone()),
+        None => Err(usage_error(format!("{} needs a value", option))),
+    }
+}
+
+fn number<T: std::str::FromStr>(option: &str, value: Option<&String>) -> Result<T, EmulatorError> {
+    match value {
+        Some(value) => value
+            .parse::<T>()
+            .map_err(|_| usage_error(format!("{} expects a number, got {}", option, value))),
+        None => Err(usage_error(format!("{} needs a value", option))),
+    }
+}
+
+// gameboy info <rom>: print the cartridge header report
+fn info(path: &str) -> Result<(), EmulatorError> {
+    let rom: Vec<u8> = std::fs::read(path)?;
+    match CartridgeHeader::parse(&rom) {
+        Some(header) => {
+            println!("{}", header);
+            Ok(())
+        }
+        None => Err(EmulatorError::TruncatedRom {
+            expected: 0x0150,
+            actual: rom.len(),
+        }),
+    }
+}
+
+// SDL window configured from the options and ./palette.cfg, ./bindings.cfg
+#[cfg(feature = "sdl")]
+fn sdl_frontend(options: &Options) -> Result<SdlWindow, EmulatorError> {
+    let mut window: SdlWindow = SdlWindow::new(options.scale).map_err(EmulatorError::Frontend)?;
+    window.set_volume(options.volume as f32 / 100.0);
+
+    // A built-in palette name or a config file, falling back to ./palette.cfg
+    match &options.palette {
+        Some(name) => match Palette::from_name(name) {
+            Some(palette) => window.set_palette(palette),
+            None => window.set_palette(Palette::load(name).map_err(EmulatorError::Config)?),
+        },
+        None => {
+            if Path::new("./palette.cfg").exists() {
+                window.set_palette(Palette::load("./palette.cfg").map_err(EmulatorError::Config)?);
+            }
+        }
+    }
+    if Path::new("./bindings.cfg").exists() {
+        window.set_bindings(Bindings::load("./bindings.cfg").map_err(EmulatorError::Config)?);
+    }
+    Ok(window)
+}
+
+#[cfg(not(feature = "sdl"))]
+fn sdl_frontend(_options: &Options) -> Result<NullFrontend, EmulatorError> {
+    Err(EmulatorError::Config(
+        "built without the sdl feature, only --headless runs are available".to_string(),
+    ))
+}
+
+fn run() -> Result<(), EmulatorError> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(|arg| arg.as_str()) == Some("info") {
+        return match args.get(1) {
+            Some(path) => info(path),
+            None => Err(usage_error("info needs a ROM".to_string())),
+        };
+    }
+
+    let options: Options = match Options::parse(&args)? {
+        Some(options) => options,
+        None => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+    };
+
+    let mut gameboy = Gameboy::new();
+
+    if let Some(save_dir) = &options.save_dir {
+        gameboy.set_save_dir(save_dir);
+    }
+    if let Some(boot_rom) = &options.boot_rom {
+        gameboy.load_boot_rom(boot_rom)?;
+    }
+    gameboy.load_rom(&options.rom)?;
+
+    // Headless runs have no window and no frame pacing
+    let mut frontend: Box<dyn Frontend> = if options.headless {
+        Box::new(NullFrontend::new())
+    } else {
+        Box::new(sdl_frontend(&options)?)
+    };
+
+    // Save the cartridge RAM even when the run loop fails, its error comes first
+    match run_loop(&mut gameboy, frontend.as_mut(), &options) {
+        Ok(()) => gameboy.save(),
+        Err(e) => {
+            if let Err(save_error) = gameboy.save() {
+                eprintln!("{}", save_error);
+            }
+            Err(e)
+        }
+    }
+}
+
+fn run_loop(gameboy: &mut Gameboy, frontend: &mut dyn Frontend, options: &Options) -> Result<(), EmulatorError> {
+    loop {
+        if options.frames.is_some_and(|frames| gameboy.frames() >= frames)
+            || options.cycles.is_some_and(|cycles| gameboy.cycles() >= cycles)
+        {
+            return Ok(());
+        }
+        let frame_ready: bool = match gameboy.step_instruction() {
+            Ok(frame_ready) => frame_ready,
+            // The locked CPU keeps the window open like the real hardware would
+            Err(e @ EmulatorError::IllegalOpcode { .. }) => {
+                eprintln!("{}", e);
+                false
+            }
+            Err(e) => return Err(e),
+        };
+
+        // Hand the frame and its audio to the frontend and poll input once per frame
+        if frame_ready {
+            // A failed flush is retried on the next one and at exit
+            if gameboy.frames().is_multiple_of(SAVE_INTERVAL_FRAMES) {
+                if let Err(e) = gameboy.save() {
+                    eprintln!("{}", e);
+                }
+            }
+            frontend.queue_audio(&gameboy.audio_samples())?;
+            let buttons: u8 = frontend.poll_input();
+            if frontend.should_quit() {
+                return Ok(());
+            }
+            gameboy.set_buttons(buttons);
+            gameboy.set_muted_channels(frontend.muted_channels());
+            frontend.present_frame(gameboy.frame_buffer())?;
+        }
+    }
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(args: &[&str]) -> Options {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        Options::parse(&args).unwrap().unwrap()
+    }
+
+    #[test]
+    fn cycle_limit_ends_a_stopped_headless_run() {
+        // STOP with no button ever pressed
+        let mut rom: Vec<u8> = vec![0u8; 0x8000];
+        rom[0x0100] = 0x10;
+        let mut gameboy: Gameboy = Gameboy::from_rom_bytes(rom).unwrap();
+
+        let options: Options = options(&["--headless", "--cycles", "100000", "game.gb"]);
+        run_loop(&mut gameboy, &mut NullFrontend::new(), &options).unwrap();
+        assert_eq!(gameboy.cycles(), 100000);
+    }
+
+    #[test]
+    fn frame_limit_ends_a_headless_run() {
+        // JR -2
+        let mut rom: Vec<u8> = vec![0u8; 0x8000];
+        rom[0x0100] = 0x18;
+        rom[0x0101] = 0xFE;
+        let mut gameboy: Gameboy = Gameboy::from_rom_bytes(rom).unwrap();
+
+        let options: Options = options(&["--headless", "--frames", "3", "game.gb"]);
+        run_loop(&mut gameboy, &mut NullFrontend::new(), &options).unwrap();
+        assert_eq!(gameboy.frames(), 3);
     }
-    while gameboy.step()? {};
-    Ok(())
-}
\ No newline at end of file
+}